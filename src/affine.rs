@@ -0,0 +1,14 @@
+//! Transforms that are fiddly to build by hand.
+use vello::kurbo::{Affine, Point};
+
+/// Rotate by `angle` radians (clockwise, since y points down) around `center`.
+pub fn rotate_around(angle: f64, center: impl Into<Point>) -> Affine {
+    let center = center.into().to_vec2();
+    Affine::translate(center) * Affine::rotate(angle) * Affine::translate(-center)
+}
+
+/// Scale by `factor` around `center`, which stays where it is.
+pub fn scale_around(factor: f64, center: impl Into<Point>) -> Affine {
+    let center = center.into().to_vec2();
+    Affine::translate(center) * Affine::scale(factor) * Affine::translate(-center)
+}
@@ -0,0 +1,121 @@
+//! Helpers for making [`Color`]s from the representations you are likely to have to hand.
+use std::{error::Error, fmt};
+
+use vello::peniko::Color;
+
+/// Parse a hex color like `#ff8800`.
+///
+/// The `#` is optional, and the short (`#f80`) and alpha (`#ff8800cc`, `#f80c`) forms are
+/// accepted.
+pub fn hex(s: &str) -> Result<Color, ParseHexError> {
+    let digits = s.strip_prefix('#').unwrap_or(s);
+    // Checking this up front also means the slicing below can't split a character.
+    if !digits.chars().all(|ch| ch.is_ascii_hexdigit()) {
+        return Err(ParseHexError::InvalidDigit);
+    }
+    let channel = |i: usize, width: usize| {
+        let value = u8::from_str_radix(&digits[i * width..(i + 1) * width], 16).unwrap();
+        // Short forms repeat each digit, so that e.g. `f` means `ff`
+        if width == 1 {
+            value * 0x11
+        } else {
+            value
+        }
+    };
+    let (width, has_alpha) = match digits.len() {
+        3 => (1, false),
+        4 => (1, true),
+        6 => (2, false),
+        8 => (2, true),
+        _ => return Err(ParseHexError::InvalidLength),
+    };
+    let a = if has_alpha { channel(3, width) } else { 255 };
+    Ok(Color::rgba8(
+        channel(0, width),
+        channel(1, width),
+        channel(2, width),
+        a,
+    ))
+}
+
+/// Make a color from red, green, blue and alpha components in the range `0..=1`, as many UI
+/// libraries use.
+///
+/// Components outside the range are clamped.
+pub fn rgba_f32([r, g, b, a]: [f32; 4]) -> Color {
+    Color::rgba(r.into(), g.into(), b.into(), a.into())
+}
+
+/// The error returned by [`hex`] when the string isn't a valid hex color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParseHexError {
+    /// The color didn't have 3, 4, 6 or 8 digits.
+    InvalidLength,
+    /// A character wasn't a hex digit.
+    InvalidDigit,
+}
+
+impl fmt::Display for ParseHexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidLength => f.write_str("hex colors must have 3, 4, 6 or 8 digits"),
+            Self::InvalidDigit => f.write_str("invalid hex digit in color"),
+        }
+    }
+}
+
+impl Error for ParseHexError {}
+
+/// Saves optional colors as `[r, g, b, a]`, since peniko's colors aren't serializable.
+#[cfg(feature = "serde")]
+pub(crate) mod serde_rgba {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use vello::peniko::Color;
+
+    pub(crate) fn serialize<S: Serializer>(
+        color: &Option<Color>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        color
+            .map(|color| [color.r, color.g, color.b, color.a])
+            .serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Color>, D::Error> {
+        let rgba = Option::<[u8; 4]>::deserialize(deserializer)?;
+        Ok(rgba.map(|[r, g, b, a]| Color::rgba8(r, g, b, a)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_forms() {
+        let orange = Color::rgb8(0xff, 0x88, 0x00);
+        assert_eq!(hex("#ff8800"), Ok(orange));
+        assert_eq!(hex("ff8800"), Ok(orange));
+        assert_eq!(hex("#FF8800"), Ok(orange));
+        assert_eq!(hex("#f80"), Ok(orange));
+        assert_eq!(hex("#ff8800cc"), Ok(Color::rgba8(0xff, 0x88, 0x00, 0xcc)));
+        assert_eq!(hex("#f80c"), Ok(Color::rgba8(0xff, 0x88, 0x00, 0xcc)));
+    }
+
+    #[test]
+    fn hex_errors() {
+        assert_eq!(hex(""), Err(ParseHexError::InvalidLength));
+        assert_eq!(hex("#"), Err(ParseHexError::InvalidLength));
+        assert_eq!(hex("#ff88"), Ok(Color::rgba8(0xff, 0xff, 0x88, 0x88)));
+        assert_eq!(hex("#ff880"), Err(ParseHexError::InvalidLength));
+        assert_eq!(hex("#ff8800ccc"), Err(ParseHexError::InvalidLength));
+        assert_eq!(hex("#ff880g"), Err(ParseHexError::InvalidDigit));
+        assert_eq!(hex("##f80"), Err(ParseHexError::InvalidDigit));
+        assert_eq!(hex("+f80"), Err(ParseHexError::InvalidDigit));
+        // Multi-byte characters are rejected rather than splitting them
+        assert_eq!(hex("#ff88é"), Err(ParseHexError::InvalidDigit));
+    }
+}
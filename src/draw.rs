@@ -0,0 +1,351 @@
+//! Drawing helpers on top of the raw [`SceneBuilder`](vello::SceneBuilder) API.
+use vello::{
+    kurbo::{Affine, BezPath, Insets, Point, Rect, RoundedRect, Shape, Stroke, Vec2},
+    peniko::{Brush, BrushRef, Color, Fill, Image, Mix},
+    SceneBuilder, SceneFragment,
+};
+
+use crate::{RenderCtx, TextSpacing};
+
+/// Which point of an image is placed at the position given to [`RenderCtx::draw_image_anchored`],
+/// and is the center of its rotation and scaling.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    #[default]
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    /// How far across and down the image the anchor is, from 0 to 1.
+    fn fraction(self) -> (f64, f64) {
+        match self {
+            Anchor::TopLeft => (0., 0.),
+            Anchor::Top => (0.5, 0.),
+            Anchor::TopRight => (1., 0.),
+            Anchor::Left => (0., 0.5),
+            Anchor::Center => (0.5, 0.5),
+            Anchor::Right => (1., 0.5),
+            Anchor::BottomLeft => (0., 1.),
+            Anchor::Bottom => (0.5, 1.),
+            Anchor::BottomRight => (1., 1.),
+        }
+    }
+}
+
+/// The number of layers used to approximate a blur in [`RenderCtx::shadow`].
+const SHADOW_STEPS: u32 = 8;
+
+impl<'a> RenderCtx<'a> {
+    /// Draw a drop shadow for `shape`, moved by `offset` and softened over `blur_radius`.
+    ///
+    /// Draw this before the shape itself, so the shape covers the shadow.
+    ///
+    /// vello has no blur, so this is an approximation: the shadow is drawn as 8 layers, each a
+    /// little bigger than the last, which fade out linearly over `blur_radius`. This works well
+    /// for rounded rectangles and other convex shapes. Each layer costs about as much as drawing
+    /// the shape with a stroke, so avoid using lots of these in one frame.
+    pub fn shadow(
+        &mut self,
+        shape: &impl Shape,
+        offset: impl Into<Vec2>,
+        blur_radius: f64,
+        color: Color,
+    ) {
+        let transform = self.current_transform() * Affine::translate(offset.into());
+        let blur_radius = blur_radius.max(0.);
+        let bounds = shape.bounding_box().inflate(blur_radius, blur_radius);
+        self.add_bounds(transform.transform_rect_bbox(bounds));
+        if blur_radius == 0. {
+            self.fill(Fill::NonZero, transform, color, None, shape);
+            return;
+        }
+
+        let opaque = Color { a: 255, ..color };
+        // Choose the alpha of each layer so that where they all overlap we get `color`'s alpha
+        let total_alpha = f64::from(color.a) / 255.;
+        let layer_alpha = 1. - (1. - total_alpha).powf(1. / f64::from(SHADOW_STEPS));
+        let clip = shape.bounding_box().inflate(blur_radius, blur_radius);
+        for step in 1..=SHADOW_STEPS {
+            let spread = blur_radius * f64::from(step) / f64::from(SHADOW_STEPS);
+            self.push_layer(Mix::Normal, layer_alpha as f32, transform, &clip);
+            self.fill(Fill::NonZero, transform, opaque, None, shape);
+            self.stroke(&Stroke::new(spread * 2.), transform, opaque, None, shape);
+            self.pop_layer();
+        }
+    }
+
+    /// Fill `shape` with `color`, fading out to transparent over `feather` beyond its edge, for
+    /// glows and soft highlights.
+    ///
+    /// This is [`RenderCtx::shadow`] without the offset, so it's the same approximation: 8
+    /// layers, each costing about as much as filling and stroking the shape.
+    pub fn soft_fill(&mut self, shape: &impl Shape, color: Color, feather: f64) {
+        self.shadow(shape, Vec2::ZERO, feather, color);
+    }
+
+    /// Draw into `rect` as if it were its own canvas, e.g. for one pane of a split view.
+    ///
+    /// The region is filled with `background`, and everything `f` draws is clipped to it.
+    /// Inside `f`, the origin is the top-left of `rect`, [`RenderCtx::screen`] reports the size
+    /// of the region, and the current transform starts as the identity. `rect` itself is drawn
+    /// with the current transform. [`RenderCtx::cursor_position`] stays in window coordinates,
+    /// but the widgets take the region into account.
+    pub fn region(&mut self, rect: Rect, background: Color, f: impl FnOnce(&mut RenderCtx)) {
+        let rect = rect.abs();
+        let mut fragment = SceneFragment::new();
+        // Bounds drawn inside the region are in its own coordinates, so we ignore them.
+        let bounds = self.state.content_bounds;
+        let transform = self.current_transform();
+        // The region's transform is applied to the whole fragment below, so inside it drawing
+        // starts with a stack of its own
+        let stack = self.state.stack.enter_region(rect.origin());
+        {
+            let mut builder = SceneBuilder::for_fragment(&mut fragment);
+            let mut screen = self.screen;
+            screen.phy_size = rect.size() * screen.drawing_scale();
+            let mut cx = RenderCtx {
+                scene_builder: &mut builder,
+                screen,
+                text_ctx: &mut *self.text_ctx,
+                window: self.window,
+                device: self.device,
+                queue: self.queue,
+                instance: self.instance,
+                state: &mut *self.state,
+            };
+            f(&mut cx);
+        }
+        let open_clips = std::mem::replace(&mut self.state.stack, stack).end_frame();
+        self.state.content_bounds = bounds;
+        self.add_local_bounds(rect);
+
+        self.push_layer(Mix::Clip, 1., transform, &rect);
+        self.fill(Fill::NonZero, transform, background, None, &rect);
+        let origin = Affine::translate(rect.origin().to_vec2());
+        self.append(&fragment, Some(transform * origin));
+        for _ in 0..open_clips {
+            self.pop_layer();
+        }
+        self.pop_layer();
+    }
+
+    /// Stroke `shape` with a line exactly `physical_width` physical pixels wide, whatever the
+    /// scale factor, e.g. for hairline borders.
+    ///
+    /// `transform` is applied inside the current transform (see [`RenderCtx::set_transform`]).
+    /// This undoes the automatic DPI scaling, so any scaling in either still applies.
+    pub fn stroke_physical<'b>(
+        &mut self,
+        physical_width: f64,
+        transform: Affine,
+        brush: impl Into<BrushRef<'b>>,
+        shape: &impl Shape,
+    ) {
+        let transform = self.current_transform() * transform;
+        let stroke = Stroke::new(physical_width / self.screen.drawing_scale());
+        self.add_stroke_bounds(shape, &stroke, transform);
+        self.stroke(&stroke, transform, brush, None, shape);
+    }
+
+    /// Move `p` to the nearest physical pixel corner, so that axis-aligned fills starting there
+    /// have crisp edges.
+    ///
+    /// This assumes `p` is drawn without any transform other than the automatic DPI scaling.
+    pub fn snap(&self, p: Point) -> Point {
+        let scale = self.screen.drawing_scale();
+        Point::new((p.x * scale).round() / scale, (p.y * scale).round() / scale)
+    }
+
+    /// Move `p` so that an axis-aligned stroke `width` logical pixels wide through it covers
+    /// whole physical pixels, for crisp borders and grid lines.
+    ///
+    /// Strokes an odd number of physical pixels wide need to be centred on a pixel, and even
+    /// widths on a pixel corner. Like [`RenderCtx::snap`], this assumes no transform.
+    pub fn snap_stroke(&self, p: Point, width: f64) -> Point {
+        let scale = self.screen.drawing_scale();
+        let phy_width = (width * scale).round().max(1.);
+        let offset = if phy_width % 2. == 1. { 0.5 } else { 0. };
+        let snap = |v: f64| ((v * scale - offset).round() + offset) / scale;
+        Point::new(snap(p.x), snap(p.y))
+    }
+
+    /// Draw `text` on a rounded box of `bg`, with `padding` around the text, e.g. for a tooltip.
+    ///
+    /// `origin` is the top-left of the text, so the box extends `padding` beyond it. Returns the
+    /// box.
+    pub fn label(
+        &mut self,
+        text: &str,
+        origin: impl Into<Point>,
+        font_size: f32,
+        fg: Color,
+        bg: Color,
+        padding: f64,
+    ) -> Rect {
+        let origin = origin.into();
+        let size = self.measure_text(font_size, text);
+        let rect = Rect::from_origin_size(origin, size).inflate(padding, padding);
+        let bg_shape = RoundedRect::from_rect(rect, padding);
+        let transform = self.current_transform();
+        self.fill(Fill::NonZero, transform, bg, None, &bg_shape);
+        self.add_local_bounds(rect);
+        self.draw_text_brush(
+            font_size,
+            origin,
+            text,
+            TextSpacing::default(),
+            Some(&Brush::Solid(fg)),
+        );
+        rect
+    }
+
+    /// Draw `image` stretched to fill `target`, keeping the corners the size they are in the
+    /// image, for scalable buttons and panels.
+    ///
+    /// `insets` are the sizes of the fixed borders in image pixels. The edges stretch along
+    /// their length and the middle stretches both ways. If `target` is too small for the
+    /// corners, they are shrunk to fit.
+    pub fn nine_patch(&mut self, image: &Image, insets: Insets, target: Rect) {
+        let target = target.abs();
+        let transform = self.current_transform();
+        let (width, height) = (image.width as f64, image.height as f64);
+        let src_x = [0., insets.x0, width - insets.x1, width];
+        let src_y = [0., insets.y0, height - insets.y1, height];
+        let scale_x = (target.width() / (insets.x0 + insets.x1)).min(1.);
+        let scale_y = (target.height() / (insets.y0 + insets.y1)).min(1.);
+        let dst_x = [
+            target.x0,
+            target.x0 + insets.x0 * scale_x,
+            target.x1 - insets.x1 * scale_x,
+            target.x1,
+        ];
+        let dst_y = [
+            target.y0,
+            target.y0 + insets.y0 * scale_y,
+            target.y1 - insets.y1 * scale_y,
+            target.y1,
+        ];
+        for col in 0..3 {
+            for row in 0..3 {
+                let src = Rect::new(src_x[col], src_y[row], src_x[col + 1], src_y[row + 1]);
+                let dst = Rect::new(dst_x[col], dst_y[row], dst_x[col + 1], dst_y[row + 1]);
+                if src.width() <= 0.
+                    || src.height() <= 0.
+                    || dst.width() <= 0.
+                    || dst.height() <= 0.
+                {
+                    continue;
+                }
+                // Map this slice of the image onto its slice of the target
+                let brush_transform = Affine::translate(dst.origin().to_vec2())
+                    * Affine::scale_non_uniform(
+                        dst.width() / src.width(),
+                        dst.height() / src.height(),
+                    )
+                    * Affine::translate(-src.origin().to_vec2());
+                self.fill(Fill::NonZero, transform, image, Some(brush_transform), &dst);
+            }
+        }
+        self.add_local_bounds(target);
+    }
+
+    /// Draw `image` with its `anchor` point at `pos`, rotated clockwise by `rotation` radians
+    /// and scaled by `scale` around that point, e.g. for sprites.
+    ///
+    /// `scale` is relative to the image's size in pixels, so 1 draws it one logical pixel per
+    /// image pixel.
+    pub fn draw_image_anchored(
+        &mut self,
+        image: &Image,
+        pos: Point,
+        anchor: Anchor,
+        rotation: f64,
+        scale: f64,
+    ) {
+        let (width, height) = (image.width as f64, image.height as f64);
+        let (fx, fy) = anchor.fraction();
+        let transform = self.current_transform()
+            * Affine::translate(pos.to_vec2())
+            * Affine::rotate(rotation)
+            * Affine::scale(scale)
+            * Affine::translate((-fx * width, -fy * height));
+        self.draw_image(image, transform);
+        self.add_bounds(transform.transform_rect_bbox(Rect::new(0., 0., width, height)));
+    }
+
+    /// Stroke a line through all of `points`, as a single path, e.g. for a line chart.
+    ///
+    /// This is much faster than stroking each segment separately, and the joins look right.
+    pub fn polyline<'b>(
+        &mut self,
+        points: &[Point],
+        stroke: &Stroke,
+        brush: impl Into<BrushRef<'b>>,
+    ) {
+        if points.is_empty() {
+            return;
+        }
+        let path = path_through(points, false);
+        let transform = self.current_transform();
+        self.add_stroke_bounds(&path, stroke, transform);
+        self.stroke(stroke, transform, brush, None, &path);
+    }
+
+    /// Fill the polygon with corners at `points`.
+    pub fn polygon<'b>(&mut self, points: &[Point], brush: impl Into<BrushRef<'b>>) {
+        if points.is_empty() {
+            return;
+        }
+        let path = path_through(points, true);
+        self.add_local_bounds(path.bounding_box());
+        let transform = self.current_transform();
+        self.fill(Fill::NonZero, transform, brush, None, &path);
+    }
+
+    /// The bounding box of everything drawn so far this frame.
+    ///
+    /// This is useful for "zoom to fit", or for cropping an exported image. Only the drawing
+    /// helpers on `RenderCtx` (like [`RenderCtx::draw_text`] and [`RenderCtx::polyline`]) are
+    /// counted, not drawing directly with the [`SceneBuilder`](vello::SceneBuilder) methods.
+    /// Returns `None` if nothing has been drawn.
+    pub fn content_bounds(&self) -> Option<Rect> {
+        self.state.content_bounds
+    }
+
+    pub(crate) fn add_bounds(&mut self, rect: Rect) {
+        let bounds = &mut self.state.content_bounds;
+        *bounds = Some(bounds.map_or(rect, |b| b.union(rect)));
+    }
+
+    /// Add the bounds of something drawn with the current transform.
+    pub(crate) fn add_local_bounds(&mut self, rect: Rect) {
+        let transform = self.current_transform();
+        self.add_bounds(transform.transform_rect_bbox(rect));
+    }
+
+    fn add_stroke_bounds(&mut self, shape: &impl Shape, stroke: &Stroke, transform: Affine) {
+        let half_width = stroke.width * 0.5;
+        let bounds = shape.bounding_box().inflate(half_width, half_width);
+        self.add_bounds(transform.transform_rect_bbox(bounds));
+    }
+}
+
+pub(crate) fn path_through(points: &[Point], close: bool) -> BezPath {
+    let mut path = BezPath::new();
+    path.move_to(points[0]);
+    for p in &points[1..] {
+        path.line_to(*p);
+    }
+    if close {
+        path.close_path();
+    }
+    path
+}
@@ -0,0 +1,99 @@
+//! Drawing into a window from an event loop that isn't snog's.
+use std::{sync::Arc, time::Instant};
+
+use vello::{
+    kurbo::Size,
+    util::{RenderContext, RenderSurface},
+    AaSupport, Renderer, RendererOptions,
+};
+use winit::window::Window;
+
+use crate::{present, AppLogic, Painter, Screen};
+
+/// Draws an [`AppLogic`] into a window you manage, for apps that already have their own winit
+/// event loop and so can't use [`App::run`](crate::App::run).
+///
+/// Call [`WindowRenderer::render_and_present`] wherever you draw, usually on
+/// `RedrawRequested`. Events are yours to handle, so [`AppLogic::event`] isn't called.
+pub struct WindowRenderer {
+    // The surface must be dropped before the window, so the fields must be in this order
+    surface: RenderSurface,
+    window: Arc<Window>,
+    render_cx: RenderContext,
+    renderer: Renderer,
+    painter: Painter,
+    start: Instant,
+}
+
+impl WindowRenderer {
+    /// Set up drawing to `window`. This finds a GPU, so it can take a little while.
+    ///
+    /// The window is shared so that it can't be dropped while it's being drawn to.
+    pub fn new(window: Arc<Window>) -> Self {
+        let mut render_cx = RenderContext::new().unwrap();
+        let size = window.inner_size();
+        let surface =
+            pollster::block_on(render_cx.create_surface(window.as_ref(), size.width, size.height))
+                .expect("couldn't draw to the window");
+        let renderer = Renderer::new(
+            &render_cx.devices[surface.dev_id].device,
+            RendererOptions {
+                surface_format: Some(surface.format),
+                timestamp_period: 1.,
+                use_cpu: false,
+                antialiasing_support: AaSupport::area_only(),
+            },
+        )
+        .expect("Couldn't create renderer");
+        Self {
+            surface,
+            window,
+            render_cx,
+            renderer,
+            painter: Painter::new(),
+            start: Instant::now(),
+        }
+    }
+
+    /// Draw a frame with `logic` and present it, blocking until it's on screen.
+    ///
+    /// The surface follows the window's size, so there's nothing to do on resize. Nothing is
+    /// drawn while the window has no area (e.g. when minimized).
+    pub fn render_and_present(&mut self, logic: &mut impl AppLogic) {
+        let size = self.window.inner_size();
+        if size.width == 0 || size.height == 0 {
+            return;
+        }
+        if (size.width, size.height) != (self.surface.config.width, self.surface.config.height) {
+            self.render_cx
+                .resize_surface(&mut self.surface, size.width, size.height);
+        }
+        let screen = Screen {
+            phy_size: Size::new(size.width as f64, size.height as f64),
+            scale_factor: self.window.scale_factor(),
+            auto_scale: true,
+        };
+        let device_handle = &self.render_cx.devices[self.surface.dev_id];
+        self.painter.state.time = self.start.elapsed();
+        self.painter.paint(
+            logic,
+            screen,
+            Some(self.window.as_ref()),
+            &device_handle.device,
+            &device_handle.queue,
+            Some(&self.render_cx.instance),
+        );
+        let params = self.painter.render_params(size.width, size.height);
+        let present_time = present(
+            device_handle,
+            &mut self.renderer,
+            &self.painter.scene,
+            &self.surface,
+            &params,
+            None,
+            logic,
+        );
+        logic.on_frame_presented(self.painter.state.frame_index, present_time);
+        self.painter.state.frame_index += 1;
+    }
+}
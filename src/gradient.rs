@@ -0,0 +1,196 @@
+//! Gradients that are awkward to build with peniko directly.
+use std::f64::consts::TAU;
+
+use vello::{
+    kurbo::{Affine, BezPath, Point, Shape},
+    peniko::{Color, Fill, Mix},
+};
+
+use crate::RenderCtx;
+
+/// The number of wedges a full turn is split into when drawing a [`SweepGradient`].
+const WEDGES_PER_TURN: f64 = 360.;
+
+/// A gradient that sweeps around a center point, for color wheels, pie charts and gauges.
+///
+/// vello can't draw peniko's sweep gradients yet, so draw these with
+/// [`RenderCtx::fill_sweep`], which approximates them with thin wedges of solid color.
+#[derive(Debug, Clone)]
+pub struct SweepGradient {
+    center: Point,
+    start_angle: f64,
+    /// Always more than `start_angle`, and at most a full turn after it.
+    end_angle: f64,
+    stops: Vec<(f32, Color)>,
+}
+
+/// Make a gradient that goes through `stops` as the angle goes from `start_angle` to
+/// `end_angle`.
+///
+/// Angles are in radians, clockwise from the positive x axis (as y points down). If
+/// `end_angle` isn't after `start_angle`, it is moved on by whole turns until it is, so
+/// `sweep(c, 0., 0., stops)` is a full circle, and a full turn closes without a gap. Stops are
+/// `(offset, color)` pairs, with offsets from 0 (the start) to 1 (the end). Outside the sweep, the
+/// nearest end color is used.
+pub fn sweep(
+    center: impl Into<Point>,
+    start_angle: f64,
+    end_angle: f64,
+    stops: &[(f32, Color)],
+) -> SweepGradient {
+    let mut span = (end_angle - start_angle).rem_euclid(TAU);
+    if span == 0. {
+        span = TAU;
+    }
+    let mut stops = stops.to_vec();
+    stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+    SweepGradient {
+        center: center.into(),
+        start_angle,
+        end_angle: start_angle + span,
+        stops,
+    }
+}
+
+impl SweepGradient {
+    /// The color at `t`, between 0 (the start angle) and 1 (the end angle).
+    fn color_at(&self, t: f64) -> Color {
+        let t = t as f32;
+        let (Some(first), Some(last)) = (self.stops.first(), self.stops.last()) else {
+            return Color::TRANSPARENT;
+        };
+        if t <= first.0 {
+            return first.1;
+        }
+        for pair in self.stops.windows(2) {
+            let ((t0, c0), (t1, c1)) = (pair[0], pair[1]);
+            if t <= t1 {
+                let frac = if t1 > t0 { (t - t0) / (t1 - t0) } else { 1. };
+                return lerp(c0, c1, frac);
+            }
+        }
+        last.1
+    }
+}
+
+impl<'a> RenderCtx<'a> {
+    /// Fill `shape` with a sweep gradient.
+    ///
+    /// The gradient is drawn as one wedge per degree, clipped to the shape, which is
+    /// indistinguishable from a smooth gradient for most uses. `transform` is applied inside the
+    /// current transform (see [`RenderCtx::set_transform`]).
+    pub fn fill_sweep(&mut self, transform: Affine, shape: &impl Shape, gradient: &SweepGradient) {
+        let transform = self.current_transform() * transform;
+        let bounds = shape.bounding_box();
+        self.add_bounds(transform.transform_rect_bbox(bounds));
+        let center = gradient.center;
+        // Far enough from the center to cover the whole shape
+        let radius = [
+            bounds.origin(),
+            Point::new(bounds.x1, bounds.y0),
+            Point::new(bounds.x0, bounds.y1),
+            Point::new(bounds.x1, bounds.y1),
+        ]
+        .iter()
+        .map(|p| p.distance(center))
+        .fold(0., f64::max)
+            + 1.;
+
+        self.push_layer(Mix::Clip, 1., transform, shape);
+        let span = gradient.end_angle - gradient.start_angle;
+        let wedges = (span / TAU * WEDGES_PER_TURN).ceil().max(1.) as u32;
+        let step = span / f64::from(wedges);
+        // Overlap wedges slightly so antialiasing doesn't leave hairline gaps between them
+        let overlap = step * 0.5;
+        for i in 0..wedges {
+            let a0 = gradient.start_angle + step * f64::from(i);
+            let t = (f64::from(i) + 0.5) / f64::from(wedges);
+            let wedge = wedge(center, radius, a0, a0 + step + overlap);
+            self.fill(Fill::NonZero, transform, gradient.color_at(t), None, &wedge);
+        }
+        // Pad the rest of the circle with the end colors, meeting opposite the middle of the sweep
+        let rest = TAU - span;
+        if rest > 0. {
+            let mid = gradient.end_angle + rest * 0.5;
+            let start = wedge(center, radius, gradient.end_angle, mid + overlap);
+            self.fill(
+                Fill::NonZero,
+                transform,
+                gradient.color_at(1.),
+                None,
+                &start,
+            );
+            let end = wedge(center, radius, mid, gradient.start_angle + TAU);
+            self.fill(Fill::NonZero, transform, gradient.color_at(0.), None, &end);
+        }
+        self.pop_layer();
+    }
+}
+
+/// A pie slice from `a0` to `a1`, with straight outer edges.
+///
+/// Slices of more than a few degrees are split so that the straight edges stay outside the
+/// circle of `radius`.
+fn wedge(center: Point, radius: f64, a0: f64, a1: f64) -> BezPath {
+    let steps = ((a1 - a0) / (TAU / 16.)).ceil().max(1.) as u32;
+    // Push the corners out so the straight edges between them don't cut into the circle
+    let radius = radius / (std::f64::consts::PI / 16.).cos();
+    let mut path = BezPath::new();
+    path.move_to(center);
+    for i in 0..=steps {
+        let angle = a0 + (a1 - a0) * f64::from(i) / f64::from(steps);
+        path.line_to(center + radius * vello::kurbo::Vec2::from_angle(angle));
+    }
+    path.close_path();
+    path
+}
+
+fn lerp(c0: Color, c1: Color, t: f32) -> Color {
+    let mix = |a: u8, b: u8| (f32::from(a) + (f32::from(b) - f32::from(a)) * t).round() as u8;
+    Color::rgba8(
+        mix(c0.r, c1.r),
+        mix(c0.g, c1.g),
+        mix(c0.b, c1.b),
+        mix(c0.a, c1.a),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+
+    fn span(start_angle: f64, end_angle: f64) -> f64 {
+        let gradient = sweep((0., 0.), start_angle, end_angle, &[]);
+        assert_eq!(gradient.start_angle, start_angle);
+        gradient.end_angle - gradient.start_angle
+    }
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "{a} != {b}");
+    }
+
+    #[test]
+    fn sweep_angles_wrap() {
+        assert_close(span(0., PI), PI);
+        // The same angle twice is a full turn, not nothing
+        assert_close(span(0., 0.), TAU);
+        // An end before the start goes the long way round, clockwise
+        assert_close(span(0., -PI / 2.), 1.5 * PI);
+        assert_close(span(1.5 * PI, PI / 2.), PI);
+        // More than a full turn is cut down to less than one
+        assert_close(span(0., 2.5 * PI), PI / 2.);
+    }
+
+    #[test]
+    fn sweep_colors_clamp_to_the_ends() {
+        let stops = [(0.75, Color::WHITE), (0.25, Color::BLACK)];
+        let gradient = sweep((0., 0.), 0., PI, &stops);
+        assert_eq!(gradient.color_at(0.), Color::BLACK);
+        assert_eq!(gradient.color_at(0.5), Color::rgb8(128, 128, 128));
+        assert_eq!(gradient.color_at(1.), Color::WHITE);
+        let empty = sweep((0., 0.), 0., PI, &[]);
+        assert_eq!(empty.color_at(0.5), Color::TRANSPARENT);
+    }
+}
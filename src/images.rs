@@ -0,0 +1,116 @@
+//! Decoding images up front, so it doesn't happen while drawing.
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    io::Cursor,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+};
+
+use image::{
+    error::{ParameterError, ParameterErrorKind},
+    io::Reader,
+    ImageError,
+};
+use vello::peniko::{Blob, Format, Image};
+
+/// The largest width or height of an image that can be drawn.
+///
+/// vello asks wgpu for its default limits, so this is the same on every GPU. Larger images are
+/// rejected by [`App::with_images`](crate::App::with_images).
+pub fn max_image_dimension() -> u32 {
+    wgpu::Limits::default().max_texture_dimension_2d
+}
+
+/// Images decoded by [`App::with_images`](crate::App::with_images), by key.
+///
+/// This is stored as a resource, so you can get it with
+/// [`RenderCtx::resources`](crate::RenderCtx::resources), but
+/// [`RenderCtx::image`](crate::RenderCtx::image) is usually easier.
+#[derive(Debug)]
+pub struct Images<K> {
+    images: HashMap<K, Image>,
+}
+
+impl<K: Hash + Eq> Images<K> {
+    pub(crate) fn new() -> Self {
+        Self {
+            images: HashMap::new(),
+        }
+    }
+
+    /// Decode all the images, in parallel on up to one thread per core, and add them to the
+    /// store.
+    pub(crate) fn load<'a>(
+        &mut self,
+        images: impl IntoIterator<Item = (K, &'a [u8])>,
+    ) -> image::ImageResult<()>
+    where
+        K: Send,
+    {
+        let (keys, bytes): (Vec<K>, Vec<&[u8]>) = images.into_iter().unzip();
+        let workers = thread::available_parallelism()
+            .map_or(1, |n| n.get())
+            .min(bytes.len());
+        // Each worker takes the next image that hasn't been started
+        let next = AtomicUsize::new(0);
+        let mut decoded: Vec<Option<Image>> = bytes.iter().map(|_| None).collect();
+        thread::scope(|scope| {
+            let handles: Vec<_> = (0..workers)
+                .map(|_| {
+                    scope.spawn(|| -> image::ImageResult<Vec<(usize, Image)>> {
+                        let mut done = vec![];
+                        loop {
+                            let idx = next.fetch_add(1, Ordering::Relaxed);
+                            let Some(bytes) = bytes.get(idx) else {
+                                return Ok(done);
+                            };
+                            done.push((idx, decode(bytes)?));
+                        }
+                    })
+                })
+                .collect();
+            for handle in handles {
+                for (idx, image) in handle.join().unwrap()? {
+                    decoded[idx] = Some(image);
+                }
+            }
+            Ok::<_, ImageError>(())
+        })?;
+        self.images.extend(
+            keys.into_iter()
+                .zip(decoded.into_iter().map(Option::unwrap)),
+        );
+        Ok(())
+    }
+
+    pub fn get(&self, key: &K) -> Option<&Image> {
+        self.images.get(key)
+    }
+}
+
+fn decode(bytes: &[u8]) -> image::ImageResult<Image> {
+    // Check the size first, since an image that's too big would be drawn blank
+    let (width, height) = Reader::new(Cursor::new(bytes))
+        .with_guessed_format()?
+        .into_dimensions()?;
+    let max = max_image_dimension();
+    if width > max || height > max {
+        return Err(ImageError::Parameter(ParameterError::from_kind(
+            ParameterErrorKind::Generic(format!(
+                "image is {width}x{height}, but images can be at most {max}x{max}"
+            )),
+        )));
+    }
+    let rgba = image::load_from_memory(bytes)?.into_rgba8();
+    let (width, height) = rgba.dimensions();
+    Ok(Image::new(
+        Blob::new(Arc::new(rgba.into_raw())),
+        Format::Rgba8,
+        width,
+        height,
+    ))
+}
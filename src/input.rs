@@ -0,0 +1,166 @@
+//! Tracking the state of the mouse and keyboard.
+use std::time::{Duration, Instant};
+
+use vello::kurbo::{Point, Rect};
+
+use crate::{ElementState, Event, MouseButton, RenderCtx, VirtualKeyCode};
+
+#[derive(Debug, Default)]
+pub(crate) struct InputState {
+    cursor: Option<Point>,
+    held_buttons: Vec<MouseButton>,
+    /// Buttons pressed since the last frame.
+    pressed_buttons: Vec<MouseButton>,
+    /// The area of the widget being dragged, if any. Widgets are identified by where they are
+    /// drawn, which is stable enough for a drag.
+    pub(crate) dragging: Option<Rect>,
+    /// Set by [`RenderCtx::reset_input_state`], for the loop to clear the state it keeps itself.
+    pub(crate) reset_pending: bool,
+}
+
+impl InputState {
+    /// Update the state for an event that has been sent to the app.
+    pub(crate) fn update(&mut self, event: &Event) {
+        match *event {
+            Event::CursorMoved { pos } => self.cursor = Some(pos),
+            Event::MouseInput {
+                state: ElementState::Pressed,
+                button,
+            } => {
+                if !self.held_buttons.contains(&button) {
+                    self.held_buttons.push(button);
+                }
+                self.pressed_buttons.push(button);
+            }
+            Event::MouseInput {
+                state: ElementState::Released,
+                button,
+            } => {
+                self.held_buttons.retain(|b| *b != button);
+                if button == MouseButton::Left {
+                    self.dragging = None;
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// Forget which buttons are held, e.g. because we won't hear about them being released.
+    pub(crate) fn reset(&mut self) {
+        self.held_buttons.clear();
+        self.pressed_buttons.clear();
+        self.dragging = None;
+    }
+
+    /// Forget the presses reported to the frame just drawn.
+    pub(crate) fn end_frame(&mut self) {
+        self.pressed_buttons.clear();
+    }
+}
+
+impl<'a> RenderCtx<'a> {
+    /// Where the mouse cursor is, or `None` if it hasn't been over the window yet.
+    ///
+    /// This is in the same coordinates as [`Event::CursorMoved`].
+    pub fn cursor_position(&self) -> Option<Point> {
+        self.state.input.cursor
+    }
+
+    /// Whether `button` is being held down.
+    pub fn is_mouse_down(&self, button: MouseButton) -> bool {
+        self.state.input.held_buttons.contains(&button)
+    }
+
+    /// Whether `button` was pressed since the last frame.
+    pub fn was_mouse_pressed(&self, button: MouseButton) -> bool {
+        self.state.input.pressed_buttons.contains(&button)
+    }
+
+    /// Forget which mouse buttons are held, and stop any drag, as if the buttons had been
+    /// released.
+    ///
+    /// snog only tracks keys for [`App::with_key_repeat`](crate::App::with_key_repeat), and
+    /// those stop repeating before the next frame. If any modifiers were held, an
+    /// [`Event::ModifiersChanged`] with no modifiers is sent then too. No key releases are sent,
+    /// so forget any keys you track yourself.
+    ///
+    /// snog does this itself when the window loses focus. Call this when you know releases will
+    /// be missed in other ways, e.g. before opening a native dialog.
+    pub fn reset_input_state(&mut self) {
+        self.state.input.reset();
+        self.state.input.reset_pending = true;
+    }
+}
+
+/// Sends repeated key presses for held keys at a steady rate, see
+/// [`App::with_key_repeat`](crate::App::with_key_repeat).
+pub(crate) struct KeyRepeat {
+    initial: Duration,
+    interval: Duration,
+    /// Held keys, and when each should next repeat.
+    held: Vec<(VirtualKeyCode, u32, Instant)>,
+}
+
+impl KeyRepeat {
+    pub(crate) fn new(initial: Duration, interval: Duration) -> Self {
+        Self {
+            initial,
+            // A zero interval would repeat forever without ever drawing a frame
+            interval: interval.max(Duration::from_millis(1)),
+            held: vec![],
+        }
+    }
+
+    /// Keep track of held keys. Returns `false` for presses of keys that are already held, which
+    /// are the platform's own repeats.
+    pub(crate) fn track(&mut self, event: &Event) -> bool {
+        let Event::KeyboardInput {
+            state,
+            keycode,
+            scancode,
+        } = *event
+        else {
+            return true;
+        };
+        let held = self.held.iter().position(|(k, _, _)| *k == keycode);
+        match (state, held) {
+            (ElementState::Pressed, Some(_)) => false,
+            (ElementState::Pressed, None) => {
+                let next = Instant::now() + self.initial;
+                self.held.push((keycode, scancode, next));
+                true
+            }
+            (ElementState::Released, held) => {
+                if let Some(idx) = held {
+                    self.held.remove(idx);
+                }
+                true
+            }
+        }
+    }
+
+    /// Forget all held keys, e.g. when the window loses focus and won't hear them released.
+    pub(crate) fn clear(&mut self) {
+        self.held.clear();
+    }
+
+    /// The next repeat that's due by `now`, if any.
+    pub(crate) fn next_due(&mut self, now: Instant) -> Option<Event> {
+        let (keycode, scancode, next) = self.held.iter_mut().find(|(_, _, next)| *next <= now)?;
+        *next += self.interval;
+        // Don't try to catch up if we've fallen far behind
+        if *next <= now {
+            *next = now + self.interval;
+        }
+        Some(Event::KeyboardInput {
+            state: ElementState::Pressed,
+            keycode: *keycode,
+            scancode: *scancode,
+        })
+    }
+
+    /// When the next repeat is due, if any keys are held.
+    pub(crate) fn next_time(&self) -> Option<Instant> {
+        self.held.iter().map(|(_, _, next)| *next).min()
+    }
+}
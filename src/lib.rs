@@ -16,20 +16,48 @@
 //! # Todo
 //!
 //! - Text
+//! - Image cursors for [`RenderCtx::set_custom_cursor`], which need a newer winit.
+//! - Redrawing only the damaged part of the window. vello rasterizes the whole target every
+//!   frame, and the surface doesn't keep its contents between frames, so this would need snog to
+//!   keep its own copy of the frame, render damaged regions separately, and blit the result to
+//!   the surface. That's a lot of machinery for a thin wrapper, so it waits on vello.
 //!
 //! # Name
 //!
 //! The word 'snog' is as an informal name for a sloppy kiss in the UK. The code in the crate may
 //! or may not be sloppy.
-use std::ops::{Deref, DerefMut};
+pub use draw::Anchor;
+pub use embed::WindowRenderer;
+#[doc(inline)]
+pub use image::RgbaImage;
+pub use images::{max_image_dimension, Images};
+pub use offscreen::{FrameTimes, TextureRenderer, ThumbnailRenderer};
+pub use pick::ObjectId;
+pub use scenes::{AppScene, SceneCommand, Scenes};
+use std::{
+    any::{Any, TypeId},
+    cell::OnceCell,
+    collections::{HashMap, VecDeque},
+    convert::Infallible,
+    hash::Hash,
+    ops::{Deref, DerefMut},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
 use text::SimpleText;
+pub use text::TextSpacing;
+pub use tonemap::ToneMapMode;
+use tonemap::ToneMapper;
 pub use vello::{kurbo, peniko, SceneBuilder, SceneFragment};
 use vello::{
-    kurbo::{Affine, Point, Size, Vec2},
-    peniko::Color,
-    util::{RenderContext, RenderSurface},
-    AaConfig, AaSupport, Renderer, RendererOptions, Scene,
+    kurbo::{Affine, Point, Rect, Size, Vec2},
+    peniko::{Brush, Color, Image, Mix},
+    util::{DeviceHandle, RenderContext, RenderSurface},
+    AaConfig, AaSupport, RenderParams, Renderer, RendererOptions, Scene,
 };
+#[cfg(target_os = "android")]
+#[doc(inline)]
+pub use winit::platform::android::activity::AndroidApp;
 use winit::{
     dpi::{LogicalPosition, PhysicalPosition},
     event::{Event as WEvent, ModifiersState, MouseScrollDelta, WindowEvent},
@@ -40,13 +68,34 @@ use winit::{
 pub use winit::{
     event::{ElementState, MouseButton, VirtualKeyCode},
     event_loop::ControlFlow,
+    monitor::{MonitorHandle, VideoMode},
+    window::{CursorIcon, Fullscreen, Theme, WindowBuilder},
 };
 
+pub mod affine;
+pub mod color;
+mod draw;
+mod embed;
+pub mod gradient;
+mod images;
+mod input;
+mod offscreen;
+mod pick;
+#[cfg(feature = "serde")]
+mod record;
+mod resize;
+mod scenes;
+mod stack;
+pub mod test;
 mod text;
+mod toast;
+mod tonemap;
+mod widgets;
 
 /// Events that you can use to update your internal state.
 #[non_exhaustive]
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Event {
     /// The 'x' at the top of the screen was pressed, or a request was made to close the window in
     /// some other way.
@@ -58,39 +107,72 @@ pub enum Event {
         state: ElementState,
         button: MouseButton,
     },
+    /// The wheel was scrolled by `delta` lines. Scrolling by pixels (e.g. on a touchpad) is
+    /// converted to lines using the height set with [`App::with_scroll_line_height`].
     MouseWheel {
         delta: f64,
     },
+    /// Fields may be added to this in future, so match it with `..`.
+    #[non_exhaustive]
     KeyboardInput {
         state: ElementState,
         keycode: VirtualKeyCode,
+        /// The hardware scancode of the key. This doesn't depend on the keyboard layout (see
+        /// [`RenderCtx::key_char`]).
+        scancode: u32,
     },
     /// The window was resized or the scale factor changed.
     Resized {
         screen: Screen,
     },
     ModifiersChanged(ModifiersState),
+    /// A display was connected or disconnected.
+    ///
+    /// winit doesn't tell us about this directly, so the list of displays is checked whenever the
+    /// window moves onto another display, changes scale, or gains focus. This means the event
+    /// may arrive some time after the display was plugged in.
+    DisplaysChanged {
+        /// The number of displays now connected.
+        count: usize,
+    },
+    /// A file was dragged onto the window and dropped. If several files are dropped at once,
+    /// there is one event for each.
+    ///
+    /// Dragged text (e.g. a selection from a browser) isn't reported, because winit has no way
+    /// to receive it.
+    DroppedFile(PathBuf),
+    /// The window's theme changed, either because the system theme changed or because of
+    /// [`RenderCtx::set_theme`].
+    ThemeChanged(Theme),
 }
 
 impl Event {
-    fn from_winit_window(evt: WindowEvent, screen: Screen) -> Option<Self> {
+    /// Whether this event came from the user's mouse or keyboard.
+    fn is_input(&self) -> bool {
+        matches!(
+            self,
+            Event::CursorMoved { .. }
+                | Event::MouseInput { .. }
+                | Event::MouseWheel { .. }
+                | Event::KeyboardInput { .. }
+        )
+    }
+
+    fn from_winit_window(evt: WindowEvent, screen: Screen, config: &Config) -> Option<Self> {
         match evt {
             WindowEvent::CloseRequested => Some(Self::CloseRequested),
-            WindowEvent::CursorMoved { position, .. } => {
-                let p: LogicalPosition<f64> = position.to_logical(screen.scale_factor);
-                Some(Event::CursorMoved {
-                    pos: Point::new(p.x, p.y),
-                })
-            }
+            WindowEvent::CursorMoved { position, .. } => Some(Event::CursorMoved {
+                pos: screen.to_logical(position),
+            }),
             WindowEvent::MouseInput { state, button, .. } => {
                 Some(Event::MouseInput { state, button })
             }
             WindowEvent::MouseWheel { delta, .. } => {
-                // TODO bit of a hack to unify lines and pixels (assumes 20px line height)
+                // TODO bit of a hack to unify lines and pixels
                 match delta {
                     MouseScrollDelta::PixelDelta(PhysicalPosition { y, .. }) => {
                         Some(Event::MouseWheel {
-                            delta: (y / 20.).ceil(),
+                            delta: (y / config.scroll_line_height).ceil(),
                         })
                     }
                     MouseScrollDelta::LineDelta(_, y) => {
@@ -102,9 +184,12 @@ impl Event {
                 input.virtual_keycode.map(|keycode| Event::KeyboardInput {
                     keycode,
                     state: input.state,
+                    scancode: input.scancode,
                 })
             }
             WindowEvent::ModifiersChanged(state) => Some(Event::ModifiersChanged(state)),
+            WindowEvent::DroppedFile(path) => Some(Event::DroppedFile(path)),
+            WindowEvent::ThemeChanged(theme) => Some(Event::ThemeChanged(theme)),
             WindowEvent::Resized { .. } | WindowEvent::ScaleFactorChanged { .. } => {
                 Some(Event::Resized { screen })
             }
@@ -114,17 +199,23 @@ impl Event {
 }
 
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Screen {
     phy_size: Size,
     scale_factor: f64,
+    /// Whether drawing is automatically scaled by the scale factor. See
+    /// [`App::with_auto_dpi_scale`].
+    auto_scale: bool,
 }
 
 impl Screen {
     /// Logical screen size
+    ///
+    /// This is the physical size if automatic DPI scaling is turned off.
     pub fn size(&self) -> Size {
         Size {
-            width: self.phy_size.width / self.scale_factor,
-            height: self.phy_size.height / self.scale_factor,
+            width: self.phy_size.width / self.drawing_scale(),
+            height: self.phy_size.height / self.drawing_scale(),
         }
     }
 
@@ -132,12 +223,96 @@ impl Screen {
     pub fn scale(&self) -> f64 {
         self.scale_factor
     }
+
+    /// Convert a position in physical pixels (e.g. from winit) into the coordinates snog uses
+    /// for drawing and events.
+    ///
+    /// This is exactly the conversion used for [`Event::CursorMoved`], so hit tests against
+    /// your own conversions line up.
+    pub fn to_logical(&self, physical: PhysicalPosition<f64>) -> Point {
+        let p: LogicalPosition<f64> = physical.to_logical(self.drawing_scale());
+        Point::new(p.x, p.y)
+    }
+
+    /// The opposite of [`Screen::to_logical`].
+    pub fn to_physical(&self, logical: Point) -> PhysicalPosition<f64> {
+        LogicalPosition::new(logical.x, logical.y).to_physical(self.drawing_scale())
+    }
+
+    /// The scale snog applies to everything drawn.
+    fn drawing_scale(&self) -> f64 {
+        if self.auto_scale {
+            self.scale_factor
+        } else {
+            1.
+        }
+    }
+}
+
+/// State that lives across frames and that can be changed from inside `render`.
+#[derive(Debug)]
+struct LoopState {
+    opacity: f32,
+    time: Duration,
+    frame_index: u64,
+    /// The size of the last frame drawn.
+    encoded_size: EncodedSize,
+    /// Keep drawing frames until this time. See [`RenderCtx::animate_for`].
+    animate_until: Option<Instant>,
+    /// See [`RenderCtx::next_wake`].
+    next_wake: Option<Instant>,
+    /// See [`RenderCtx::interpolation_alpha`].
+    interpolation_alpha: f64,
+    /// See [`RenderCtx::alpha_mode`].
+    alpha_mode: Option<wgpu::CompositeAlphaMode>,
+    /// Whether anything was drawn this frame.
+    drawn: bool,
+    /// See [`RenderCtx::content_bounds`].
+    content_bounds: Option<Rect>,
+    toast: Option<toast::Toast>,
+    /// The characters that keys have been seen to type, by scancode.
+    key_chars: HashMap<u32, char>,
+    input: input::InputState,
+    pick: pick::PickState,
+    stack: stack::DrawStack,
+    /// Resources registered with [`App::with_resource`], by type.
+    resources: HashMap<TypeId, Box<dyn Any>>,
+}
+
+impl LoopState {
+    fn new() -> Self {
+        Self {
+            opacity: 1.,
+            time: Duration::ZERO,
+            frame_index: 0,
+            encoded_size: EncodedSize::default(),
+            animate_until: None,
+            next_wake: None,
+            interpolation_alpha: 1.,
+            alpha_mode: None,
+            drawn: false,
+            content_bounds: None,
+            toast: None,
+            key_chars: HashMap::new(),
+            input: input::InputState::default(),
+            pick: pick::PickState::default(),
+            stack: stack::DrawStack::default(),
+            resources: HashMap::new(),
+        }
+    }
 }
 
 pub struct RenderCtx<'a> {
     scene_builder: &'a mut SceneBuilder<'a>,
     screen: Screen,
     text_ctx: &'a mut SimpleText,
+    /// `None` when rendering offscreen.
+    window: Option<&'a Window>,
+    device: &'a wgpu::Device,
+    queue: &'a wgpu::Queue,
+    /// `None` when drawing with a device that belongs to someone else.
+    instance: Option<&'a wgpu::Instance>,
+    state: &'a mut LoopState,
 }
 
 impl<'a> RenderCtx<'a> {
@@ -145,15 +320,357 @@ impl<'a> RenderCtx<'a> {
         self.screen
     }
 
+    /// The time since the app started.
+    ///
+    /// When rendering frames offscreen this advances by exactly the timestep each frame, so the
+    /// output is reproducible.
+    pub fn time(&self) -> Duration {
+        self.state.time
+    }
+
+    /// How far the clock is between the last fixed update and the next one, from 0 to 1, for
+    /// drawing a state between the previous and current ones. See [`App::with_fixed_timestep`].
+    ///
+    /// This is always 1 without a fixed timestep.
+    pub fn interpolation_alpha(&self) -> f64 {
+        self.state.interpolation_alpha
+    }
+
+    /// The image loaded with [`App::with_images`] under `key`.
+    ///
+    /// Images are cheap to clone, as the pixel data is shared.
+    pub fn image<K: Hash + Eq + 'static>(&self, key: &K) -> Option<Image> {
+        self.state
+            .resources
+            .get(&TypeId::of::<Images<K>>())
+            .and_then(|r| r.downcast_ref::<Images<K>>())
+            .and_then(|images| images.get(key))
+            .cloned()
+    }
+
+    /// The character that the key with `scancode` types on the user's keyboard layout, without
+    /// any modifiers held, e.g. for showing keybindings with the right symbols.
+    ///
+    /// winit can't look this up from the layout, so this is learned by watching which character
+    /// each key types. It returns `None` for keys that haven't yet been pressed on their own, and
+    /// for keys that don't type anything (like arrows).
+    pub fn key_char(&self, scancode: u32) -> Option<char> {
+        self.state.key_chars.get(&scancode).copied()
+    }
+
+    /// The index of the frame being drawn, counting presented frames from 0.
+    ///
+    /// Frames that aren't presented (see [`App::with_skip_empty_frames`]) don't count. When
+    /// rendering frames offscreen this is the index of the image.
+    pub fn frame_index(&self) -> u64 {
+        self.state.frame_index
+    }
+
+    /// The resource of type `R` registered with [`App::with_resource`], or `None` if there
+    /// isn't one.
+    pub fn resources<R: Any>(&mut self) -> Option<&mut R> {
+        self.state
+            .resources
+            .get_mut(&TypeId::of::<R>())
+            .and_then(|r| r.downcast_mut::<R>())
+    }
+
+    /// How big the last frame was once encoded for vello.
+    ///
+    /// The current frame can't be measured until it's finished, so this is always one frame
+    /// behind (and all zeros for the first frame). See also
+    /// [`App::with_path_segment_warning`].
+    pub fn encoded_size(&self) -> EncodedSize {
+        self.state.encoded_size
+    }
+
+    /// The limits of the GPU device being drawn with, e.g. the largest texture it supports.
+    ///
+    /// For images, see [`max_image_dimension`].
+    pub fn device_limits(&self) -> wgpu::Limits {
+        self.device.limits()
+    }
+
+    /// When the event loop is next due to wake up by itself, if it's waiting for a deadline
+    /// (e.g. with [`LoopMode::Reactive`] and a resize debounce, a key repeat, or soft vsync).
+    ///
+    /// This is the schedule the loop set after the last batch of events. It's `None` when the
+    /// loop is waiting for an event with no deadline, or isn't waiting at all because it draws
+    /// continuously.
+    pub fn next_wake(&self) -> Option<Instant> {
+        self.state.next_wake
+    }
+
+    /// Block until the GPU has finished all the work it has been given so far.
+    ///
+    /// Use this before reading anything back from the GPU, or before carefully timed work like
+    /// resizing. It can take a whole frame's worth of time, so don't call it unless you need to.
+    pub fn wait_for_gpu(&self) {
+        self.device.poll(wgpu::Maintain::Wait);
+    }
+
+    /// A summary of the GPU resources wgpu has allocated (buffers, textures, bind groups, etc.),
+    /// for tracking down leaks.
+    ///
+    /// wgpu only exposes this as a debug dump, so this is meant for printing or logging rather
+    /// than parsing. Compare reports from a few seconds apart: counts that keep growing point to
+    /// a leak. Returns `None` when drawing with a [`TextureRenderer`], where the device isn't
+    /// snog's.
+    pub fn gpu_memory_report(&self) -> Option<String> {
+        self.instance
+            .map(|instance| format!("{:#?}", instance.generate_report()))
+    }
+
+    /// Force the window decorations to a light or dark theme, or follow the system theme with
+    /// `None`. See [`App::with_theme`].
+    ///
+    /// Does nothing when rendering offscreen.
+    pub fn set_theme(&self, theme: Option<Theme>) {
+        if let Some(window) = self.window {
+            window.set_theme(theme);
+        }
+    }
+
+    /// Show one of the system's standard mouse cursors over the window.
+    ///
+    /// Does nothing when rendering offscreen.
+    pub fn set_cursor_icon(&self, icon: CursorIcon) {
+        if let Some(window) = self.window {
+            window.set_cursor_icon(icon);
+        }
+    }
+
+    /// Show a mouse cursor made from `rgba`, a `width` by `height` image in straight alpha,
+    /// with its click point at `hotspot` pixels from the top-left. `fallback` is shown where
+    /// image cursors aren't supported.
+    ///
+    /// The cursor is drawn by the OS, so unlike drawing one yourself it keeps up with the
+    /// mouse and works outside the window. The version of winit snog uses can't set image
+    /// cursors on any platform yet, so for now this always shows `fallback`.
+    ///
+    /// # Panics
+    ///
+    /// If `rgba` isn't `width * height * 4` bytes, or `hotspot` is outside the image.
+    pub fn set_custom_cursor(
+        &self,
+        rgba: &[u8],
+        width: u32,
+        height: u32,
+        hotspot: Point,
+        fallback: CursorIcon,
+    ) {
+        assert_eq!(
+            rgba.len(),
+            width as usize * height as usize * 4,
+            "cursor image is the wrong size"
+        );
+        assert!(
+            Rect::new(0., 0., width as f64, height as f64).contains(hotspot),
+            "cursor hotspot is outside the image"
+        );
+        self.set_cursor_icon(fallback);
+    }
+
+    /// How the window's surface is composited with what's behind it, or `None` when rendering
+    /// offscreen. See [`App::with_alpha_mode`].
+    ///
+    /// `Auto` means wgpu chose, which is opaque or inherits the platform's choice.
+    pub fn alpha_mode(&self) -> Option<wgpu::CompositeAlphaMode> {
+        self.state.alpha_mode
+    }
+
+    /// The theme of the window decorations, if the platform reports it.
+    pub fn theme(&self) -> Option<Theme> {
+        self.window.and_then(|window| window.theme())
+    }
+
+    /// Change the window title, e.g. to show the name of the open file.
+    ///
+    /// Does nothing when rendering offscreen.
+    pub fn set_title(&self, title: &str) {
+        if let Some(window) = self.window {
+            window.set_title(title);
+        }
+    }
+
+    /// Whether the window is maximized. Always `false` when rendering offscreen.
+    pub fn is_maximized(&self) -> bool {
+        self.window.is_some_and(|w| w.is_maximized())
+    }
+
+    /// Whether the window is minimized, or `None` if the platform can't tell us (Wayland, iOS,
+    /// Android, the web) or we're rendering offscreen.
+    pub fn is_minimized(&self) -> Option<bool> {
+        self.window.and_then(|w| w.is_minimized())
+    }
+
+    /// The window's fullscreen mode, or `None` if it isn't fullscreen.
+    pub fn fullscreen(&self) -> Option<Fullscreen> {
+        self.window.and_then(|w| w.fullscreen())
+    }
+
+    /// Make the window fullscreen, or windowed again with `None`.
+    ///
+    /// - `Fullscreen::Borderless(monitor)` covers the given monitor (or the current one if
+    ///   `None`) without changing its video mode.
+    /// - `Fullscreen::Exclusive(mode)` switches the monitor the mode came from to that
+    ///   resolution and refresh rate, which games often want. Get modes from
+    ///   [`MonitorHandle::video_modes`] on one of [`RenderCtx::monitors`].
+    ///
+    /// Does nothing when rendering offscreen.
+    pub fn set_fullscreen(&self, fullscreen: Option<Fullscreen>) {
+        if let Some(window) = self.window {
+            window.set_fullscreen(fullscreen);
+        }
+    }
+
+    /// All the monitors connected to the system, or none when rendering offscreen.
+    pub fn monitors(&self) -> Vec<MonitorHandle> {
+        self.window
+            .map(|w| w.available_monitors().collect())
+            .unwrap_or_default()
+    }
+
+    /// Show or hide the native title bar and borders at runtime, e.g. for an "immersive mode".
+    ///
+    /// If this changes the size of the window's contents, you'll get an [`Event::Resized`] as
+    /// usual. Does nothing when rendering offscreen.
+    pub fn set_decorations(&self, decorations: bool) {
+        if let Some(window) = self.window {
+            window.set_decorations(decorations);
+        }
+    }
+
+    /// Whether the window has a native title bar and borders.
+    pub fn is_decorated(&self) -> bool {
+        self.window.is_some_and(|w| w.is_decorated())
+    }
+
+    /// Keep drawing frames for at least `duration`, e.g. while something is animating.
+    ///
+    /// This only matters in [`LoopMode::Reactive`], where frames are otherwise only drawn in
+    /// response to events. Once the time is up the loop goes back to waiting for events. Calling
+    /// this again before then extends the time if the new deadline is later.
+    pub fn animate_for(&mut self, duration: Duration) {
+        let deadline = Instant::now() + duration;
+        let until = self.state.animate_until.get_or_insert(deadline);
+        *until = (*until).max(deadline);
+    }
+
+    /// The opacity of the whole window, from 0 (invisible) to 1 (opaque).
+    pub fn window_opacity(&self) -> f32 {
+        self.state.opacity
+    }
+
+    /// Dim the whole window, e.g. for a fade-in on startup.
+    ///
+    /// winit has no window opacity API, so this is done by compositing the scene with the given
+    /// alpha over a transparent background, and asking the window to be transparent. Whether the
+    /// desktop shows through depends on the platform:
+    ///
+    /// - Windows, macOS and Wayland support runtime transparency.
+    /// - X11 only supports it if the compositor does, and even then the window may need to have
+    ///   been created transparent.
+    /// - iOS, Android and the web don't support it, so the content is dimmed against black
+    ///   instead.
+    pub fn set_window_opacity(&mut self, opacity: f32) {
+        let opacity = opacity.clamp(0., 1.);
+        if let Some(window) = self.window {
+            if opacity < 1. && self.state.opacity >= 1. {
+                window.set_transparent(true);
+            }
+        }
+        self.state.opacity = opacity;
+    }
+
+    /// Draw `content` at `size` with its top-left at `loc`.
+    ///
+    /// Text is antialiased exactly like shapes. vello picks one antialiasing method for a whole
+    /// frame rather than for each glyph run or fill, and snog always uses area coverage, which
+    /// gives smooth edges at any size, so there's no separate quality setting for text. Glyphs
+    /// aren't hinted, so small text can look a little soft on low-DPI screens.
     pub fn draw_text(&mut self, size: f32, loc: impl Into<Point>, content: &str) {
-        let sf = self.screen.scale();
-        let position = Vec2::new(0., f64::from(size) * sf) + loc.into().to_vec2();
+        self.draw_text_brush(size, loc.into(), content, TextSpacing::default(), None)
+    }
+
+    /// The size of the box that [`RenderCtx::draw_text`] draws `content` in.
+    pub fn measure_text(&self, size: f32, content: &str) -> Size {
+        self.measure_text_spaced(size, content, TextSpacing::default())
+    }
+
+    /// Draw `content` like [`RenderCtx::draw_text`], with the letters and lines spaced out by
+    /// `spacing`, e.g. to match a design.
+    pub fn draw_text_spaced(
+        &mut self,
+        size: f32,
+        loc: impl Into<Point>,
+        content: &str,
+        spacing: TextSpacing,
+    ) {
+        self.draw_text_brush(size, loc.into(), content, spacing, None)
+    }
+
+    /// The size of the box that [`RenderCtx::draw_text_spaced`] draws `content` in.
+    pub fn measure_text_spaced(&self, size: f32, content: &str, spacing: TextSpacing) -> Size {
+        self.text_ctx.measure(None, size, spacing, content)
+    }
+
+    /// Draw `spans` of text one after another, each in its own color, e.g. for syntax
+    /// highlighting.
+    ///
+    /// `origin` is the top-left of the first line, like [`RenderCtx::draw_text`]. Spans can
+    /// contain newlines, which start the next line back at `origin`. Color emoji aren't drawn,
+    /// since vello can't draw color glyphs yet.
+    pub fn rich_text(
+        &mut self,
+        spans: &[(String, Color)],
+        origin: impl Into<Point>,
+        font_size: f32,
+    ) {
+        let origin = origin.into();
+        let line_height = f64::from(self.text_ctx.line_height(None, font_size));
+        let mut pen = Vec2::ZERO;
+        for (text, color) in spans {
+            let brush = Brush::Solid(*color);
+            for (idx, line) in text.split('\n').enumerate() {
+                if idx > 0 {
+                    pen = Vec2::new(0., pen.y + line_height);
+                }
+                if line.is_empty() {
+                    continue;
+                }
+                self.draw_text_brush(
+                    font_size,
+                    origin + pen,
+                    line,
+                    TextSpacing::default(),
+                    Some(&brush),
+                );
+                pen.x += self.measure_text(font_size, line).width;
+            }
+        }
+    }
+
+    fn draw_text_brush(
+        &mut self,
+        size: f32,
+        loc: Point,
+        content: &str,
+        spacing: TextSpacing,
+        brush: Option<&Brush>,
+    ) {
+        self.state.drawn = true;
+        let text_size = self.measure_text_spaced(size, content, spacing);
+        self.add_local_bounds(Rect::from_origin_size(loc, text_size));
+        // The fragment is already scaled by the scale factor, so we don't scale again here
+        let position = Vec2::new(0., f64::from(size)) + loc.to_vec2();
         self.text_ctx.add(
             self.scene_builder,
             None,
             size,
-            None,
-            Affine::scale(sf).then_translate(position),
+            spacing,
+            brush,
+            self.current_transform() * Affine::translate(position),
             content,
         )
     }
@@ -168,6 +685,9 @@ impl<'a> Deref for RenderCtx<'a> {
 
 impl<'a> DerefMut for RenderCtx<'a> {
     fn deref_mut(&mut self) -> &mut Self::Target {
+        // Every drawing method on `SceneBuilder` takes `&mut self`, so this is a good enough way
+        // to tell whether anything was drawn.
+        self.state.drawn = true;
         &mut self.scene_builder
     }
 }
@@ -179,12 +699,380 @@ pub trait AppLogic {
             *cf = ControlFlow::Exit;
         }
     }
+
+    /// Advance the app's state by `timestep`, with [`App::with_fixed_timestep`].
+    ///
+    /// This is called as many times as it takes to catch up with the clock before each frame
+    /// is drawn, including frames rendered offscreen with [`App::render_frames`].
+    fn fixed_update(&mut self, timestep: Duration) {
+        let _ = timestep;
+    }
+
+    /// Build the final scene from the `fragment` drawn in [`AppLogic::render`].
+    ///
+    /// The default just appends `fragment` with the `scale` transform, which accounts for the
+    /// screen's scale factor. Override this to layer cached fragments of your own around it, each
+    /// with its own transform (remember to include `scale`). Anything snog draws on top, like
+    /// toasts, is added afterwards.
+    fn compose(&mut self, builder: &mut SceneBuilder, fragment: &SceneFragment, scale: Affine) {
+        builder.append(fragment, Some(scale));
+    }
+
+    /// Called once a frame has been presented to the window.
+    ///
+    /// `frame_index` counts presented frames from 0, and `present_time` is when presenting
+    /// finished. This is useful for lining up an animation clock with the display, or spotting
+    /// dropped frames.
+    fn on_frame_presented(&mut self, frame_index: u64, present_time: Instant) {
+        let _ = (frame_index, present_time);
+    }
+
+    /// Called before vello draws each frame to the window, with the surface texture's `target`.
+    ///
+    /// vello replaces the whole surface, so anything drawn to `target` here is covered up. This
+    /// is for preparing your own GPU resources, e.g. for [`AppLogic::post_render`].
+    fn pre_render(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        target: &wgpu::TextureView,
+    ) {
+        let _ = (device, queue, target);
+    }
+
+    /// Called after vello has drawn each frame to the window's surface texture `target`, and
+    /// before it's presented, for effects vello can't do, like a bloom pass.
+    ///
+    /// Submit your own command buffers to `queue`. Neither hook is called when rendering
+    /// offscreen.
+    fn post_render(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        target: &wgpu::TextureView,
+    ) {
+        let _ = (device, queue, target);
+    }
+}
+
+/// When the app draws new frames.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LoopMode {
+    /// Draw frames as fast as possible (or as fast as vsync allows), for apps that are always
+    /// animating.
+    #[default]
+    Continuous,
+    /// Only draw a frame after an event, or while animating (see [`RenderCtx::animate_for`]).
+    /// This saves power for apps that are mostly still.
+    Reactive,
+}
+
+/// Why a frame wasn't drawn, from [`App::on_render_skipped`].
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SkipReason {
+    /// In [`LoopMode::Reactive`], nothing happened that needed a new frame.
+    NothingChanged,
+    /// The window is completely hidden, e.g. behind another window or on another workspace.
+    Occluded,
+    /// It's too soon after the last frame, because of [`App::with_soft_vsync`] or
+    /// [`App::with_idle_timeout`].
+    Paced,
+    /// The window is still being resized (see [`App::with_resize_debounce`]).
+    ResizePending,
+    /// The window has no area, e.g. because it's minimized.
+    ZeroSize,
+    /// `render` drew nothing and [`App::with_skip_empty_frames`] is on.
+    EmptyScene,
+}
+
+/// The [`AppLogic`] for an app made with [`App::from_fn`].
+pub struct FromFn<F>(F);
+
+impl<F: FnMut(&mut RenderCtx)> AppLogic for FromFn<F> {
+    fn render<'a>(&'a mut self, cx: &'a mut RenderCtx<'a>) {
+        (self.0)(cx)
+    }
+}
+
+/// How much work a frame is for vello, from [`RenderCtx::encoded_size`].
+///
+/// vello has fixed size buffers, and scenes that are too big for them are drawn wrongly. This
+/// lets you see how close you are.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct EncodedSize {
+    pub paths: u32,
+    /// Lines and curves making up the paths. This is usually what runs out first.
+    pub path_segments: u32,
+    /// Fills, strokes, images, and layers.
+    pub draw_objects: u32,
+    pub clips: u32,
+}
+
+impl EncodedSize {
+    fn of(scene: &Scene) -> Self {
+        let encoding = scene.data();
+        Self {
+            paths: encoding.n_paths,
+            path_segments: encoding.n_path_segments,
+            draw_objects: encoding.draw_tags.len() as u32,
+            clips: encoding.n_clips,
+        }
+    }
+}
+
+/// Settings chosen when building the [`App`].
+///
+/// Get them with [`App::config`] and apply them to another app with [`App::with_config`], e.g.
+/// to save them between runs with the `serde` feature. Callbacks (like
+/// [`App::with_event_filter`]) aren't settings, so they aren't included.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Config {
+    scroll_line_height: f64,
+    app_id: Option<String>,
+    skip_taskbar: bool,
+    low_latency: bool,
+    auto_dpi_scale: bool,
+    skip_empty_frames: bool,
+    loop_mode: LoopMode,
+    resize_debounce: Option<Duration>,
+    path_segment_warning: Option<u32>,
+    soft_vsync: Option<SoftVsync>,
+    /// wgpu's types aren't serializable, so this has to be set again after loading.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    backends: Option<wgpu::Backends>,
+    /// The delay before a held key starts repeating, and the time between repeats.
+    key_repeat: Option<(Duration, Duration)>,
+    theme: Option<Theme>,
+    /// How long without input before the app is idle, and the frame rate when idle.
+    idle_timeout: Option<(Duration, u32)>,
+    event_logging: bool,
+    tone_mapping: ToneMapMode,
+    max_events_per_frame: Option<usize>,
+    fixed_timestep: Option<Duration>,
+    pipeline_warmup: bool,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    alpha_mode: Option<wgpu::CompositeAlphaMode>,
+    reduced_motion_detection: bool,
+    #[cfg_attr(feature = "serde", serde(with = "color::serde_rgba"))]
+    base_color: Option<Color>,
+}
+
+/// The rate to pace frames to, see [`App::with_soft_vsync`].
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+enum SoftVsync {
+    Monitor,
+    Millihertz(u32),
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            scroll_line_height: 20.,
+            app_id: None,
+            skip_taskbar: false,
+            low_latency: false,
+            auto_dpi_scale: true,
+            skip_empty_frames: false,
+            loop_mode: LoopMode::Continuous,
+            resize_debounce: None,
+            path_segment_warning: None,
+            soft_vsync: None,
+            backends: None,
+            key_repeat: None,
+            theme: None,
+            idle_timeout: None,
+            event_logging: false,
+            tone_mapping: ToneMapMode::None,
+            max_events_per_frame: None,
+            fixed_timestep: None,
+            pipeline_warmup: false,
+            alpha_mode: None,
+            reduced_motion_detection: false,
+            base_color: None,
+        }
+    }
+}
+
+/// The most fixed updates run before one frame, see [`App::with_fixed_timestep`].
+const MAX_FIXED_UPDATES: u32 = 8;
+
+/// Turns the user's `render` into a scene that vello can draw.
+///
+/// This is shared between drawing to the window and drawing offscreen.
+struct Painter {
+    text: SimpleText,
+    state: LoopState,
+    scene: Scene,
+    fragment: SceneFragment,
+    /// `fragment` with the clips the user left open closed, when there are any.
+    balanced: SceneFragment,
+    /// Things snog draws on top of the user's content, like toasts.
+    overlay: SceneFragment,
+    /// What the scene is drawn over, if not the default. An [`App`] sets this from its config
+    /// before drawing, see [`App::with_base_color`].
+    base_color: Option<Color>,
+}
+
+impl Painter {
+    fn new() -> Self {
+        Self {
+            text: SimpleText::new(),
+            state: LoopState::new(),
+            scene: Scene::new(),
+            fragment: SceneFragment::new(),
+            balanced: SceneFragment::new(),
+            overlay: SceneFragment::new(),
+            base_color: None,
+        }
+    }
+
+    /// Run the user's `render` and compose the result into `self.scene`.
+    fn paint(
+        &mut self,
+        logic: &mut impl AppLogic,
+        screen: Screen,
+        window: Option<&Window>,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        instance: Option<&wgpu::Instance>,
+    ) {
+        let mut builder = SceneBuilder::for_fragment(&mut self.fragment);
+
+        // https://github.com/linebender/vello/issues/291
+        // TODO remove after issue is resolved.
+        {
+            // Transparent, so it can't be seen over a base color or in exported images
+            let brush = vello::peniko::Brush::Solid(Color::TRANSPARENT);
+            builder.fill(
+                vello::peniko::Fill::NonZero,
+                Affine::IDENTITY,
+                &brush,
+                None,
+                &vello::kurbo::Rect::new(0., 0., 10., 10.),
+            );
+        }
+        self.state.drawn = false;
+        self.state.content_bounds = None;
+        self.state.pick.begin_frame();
+        let mut ctx = RenderCtx {
+            scene_builder: &mut builder,
+            screen,
+            text_ctx: &mut self.text,
+            window,
+            device,
+            queue,
+            instance,
+            state: &mut self.state,
+        };
+        logic.render(&mut ctx);
+        let open_clips = self.state.stack.end_frame();
+        // A clip left open would otherwise be closed by the `pop_layer` for the opacity below,
+        // and cover the overlay too. `builder` can't be used after `logic.render`, so we close
+        // them in a copy of the fragment instead.
+        let fragment = if open_clips > 0 {
+            let mut builder = SceneBuilder::for_fragment(&mut self.balanced);
+            builder.append(&self.fragment, None);
+            for _ in 0..open_clips {
+                builder.pop_layer();
+            }
+            &self.balanced
+        } else {
+            &self.fragment
+        };
+
+        // `logic.render` borrows `ctx` for as long as it lives, so we need a new one here
+        let bounds = self.state.content_bounds;
+        let mut builder = SceneBuilder::for_fragment(&mut self.overlay);
+        let mut ctx = RenderCtx {
+            scene_builder: &mut builder,
+            screen,
+            text_ctx: &mut self.text,
+            window,
+            device,
+            queue,
+            instance,
+            state: &mut self.state,
+        };
+        ctx.draw_toast();
+        // Don't count the overlay as content
+        self.state.content_bounds = bounds;
+
+        let opacity = self.state.opacity;
+        {
+            let mut builder = SceneBuilder::for_scene(&mut self.scene);
+            if opacity < 1. {
+                builder.push_layer(
+                    Mix::Normal,
+                    opacity,
+                    Affine::IDENTITY,
+                    &Rect::from_origin_size(Point::ZERO, screen.phy_size),
+                );
+            }
+            // We apply scaling to the fragment to account for screen scale factor
+            let scale = Affine::scale(screen.drawing_scale());
+            logic.compose(&mut builder, fragment, scale);
+            builder.append(&self.overlay, Some(scale));
+            if opacity < 1. {
+                builder.pop_layer();
+            }
+        }
+        self.state.encoded_size = EncodedSize::of(&self.scene);
+        self.state.input.end_frame();
+    }
+
+    fn render_params(&self, width: u32, height: u32) -> RenderParams {
+        // If the user specifies a base color in the CLI we use that. Otherwise we use any
+        // color specified by the scene. The default is black.
+        RenderParams {
+            // A translucent window needs a transparent background to show through
+            base_color: self.base_color.unwrap_or(if self.state.opacity < 1. {
+                Color::TRANSPARENT
+            } else {
+                Color::BLACK
+            }),
+            width,
+            height,
+            // This applies to text and shapes alike, vello can't mix methods in one frame
+            antialiasing_method: AaConfig::Area,
+        }
+    }
+}
+
+/// Callbacks registered when building the [`App`].
+#[derive(Default)]
+struct Hooks {
+    slow_frame: Option<(Duration, Box<dyn Fn(Duration)>)>,
+    window_builder: Option<Box<dyn FnOnce(WindowBuilder) -> WindowBuilder>>,
+    event_filter: Option<Box<dyn FnMut(Event) -> Option<Event>>>,
+    /// Exit after this many frames, see [`App::run_frames`].
+    frame_limit: Option<u64>,
+    capture_last_frame: Option<Box<dyn FnOnce(RgbaImage)>>,
+    render_skipped: Option<Box<dyn Fn(SkipReason)>>,
+    #[cfg(feature = "serde")]
+    recorder: Option<record::Recorder>,
+    #[cfg(feature = "serde")]
+    replayer: Option<record::Replayer>,
 }
 
 pub struct App<T> {
     logic: T,
     screen: Option<Screen>,
-    text: SimpleText,
+    painter: Painter,
+    config: Config,
+    hooks: Hooks,
+    start: Instant,
+    last_frame: Option<Instant>,
+    /// Frames drawn, including empty ones that weren't presented, for [`App::run_frames`].
+    frames_run: u64,
+    /// How much of the time since the start has been covered by fixed updates.
+    simulated: Duration,
+    /// The system's setting, once read, see [`App::prefers_reduced_motion`].
+    reduced_motion: OnceCell<Option<bool>>,
 }
 
 impl<T: 'static + Default> App<T> {
@@ -193,65 +1081,538 @@ impl<T: 'static + Default> App<T> {
     }
 }
 
+impl<F: FnMut(&mut RenderCtx) + 'static> App<FromFn<F>> {
+    /// Make an app that just draws with `f`, for quick sketches and tests where you don't need
+    /// your own [`AppLogic`].
+    ///
+    /// The app closes when the window is closed.
+    pub fn from_fn(f: F) -> Self {
+        Self::new_with_data(FromFn(f))
+    }
+}
+
 impl<T: 'static> App<T> {
     pub fn new_with_data(user_data: T) -> Self {
         Self {
             logic: user_data,
             screen: None,
-            text: SimpleText::new(),
+            painter: Painter::new(),
+            config: Config::default(),
+            hooks: Hooks::default(),
+            start: Instant::now(),
+            last_frame: None,
+            frames_run: 0,
+            simulated: Duration::ZERO,
+            reduced_motion: OnceCell::new(),
+        }
+    }
+
+    /// Register a resource that lives as long as the app, for things like decoded images or
+    /// prebuilt fragments that you don't want to keep in your app data.
+    ///
+    /// Get it back during `render` with [`RenderCtx::resources`]. There can be one resource of
+    /// each type: registering another replaces the first.
+    pub fn with_resource<R: Any>(mut self, resource: R) -> Self {
+        self.painter
+            .state
+            .resources
+            .insert(TypeId::of::<R>(), Box::new(resource));
+        self
+    }
+
+    /// Decode images (PNG or JPEG) up front, so that it doesn't cause stutters while drawing.
+    ///
+    /// The images are decoded in parallel. Draw them by getting them back with
+    /// [`RenderCtx::image`] and passing them to `draw_image`. Calling this again with the same
+    /// key type adds to the images already loaded.
+    ///
+    /// Returns an error for images too big to draw (see [`max_image_dimension`]), rather than
+    /// drawing them blank.
+    pub fn with_images<'b, K: Hash + Eq + Send + 'static>(
+        mut self,
+        images: impl IntoIterator<Item = (K, &'b [u8])>,
+    ) -> image::ImageResult<Self> {
+        let resources = &mut self.painter.state.resources;
+        resources
+            .entry(TypeId::of::<Images<K>>())
+            .or_insert_with(|| Box::new(Images::<K>::new()) as Box<dyn Any>)
+            .downcast_mut::<Images<K>>()
+            .unwrap()
+            .load(images)?;
+        Ok(self)
+    }
+
+    /// The settings chosen with the other `with_*` methods.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Whether the user has asked the system to reduce motion, so you can turn animations off,
+    /// or `None` if we can't tell.
+    ///
+    /// This is always `None` unless turned on with [`App::with_reduced_motion_detection`]. It's
+    /// best effort: it reads GNOME's "enable-animations" setting on Linux, "Reduce motion" on
+    /// macOS, and "Animate controls and elements inside windows" on Windows. The setting is
+    /// read the first time this is called, and remembered after that.
+    pub fn prefers_reduced_motion(&self) -> Option<bool> {
+        if !self.config.reduced_motion_detection {
+            return None;
         }
+        *self
+            .reduced_motion
+            .get_or_init(system_prefers_reduced_motion)
+    }
+
+    /// Replace all the settings at once, e.g. with ones saved from an earlier run.
+    pub fn with_config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// The number of pixels that one line of scrolling corresponds to (default 20).
+    ///
+    /// Used to convert pixel scroll deltas into the line deltas reported by
+    /// [`Event::MouseWheel`].
+    pub fn with_scroll_line_height(mut self, height: f64) -> Self {
+        self.config.scroll_line_height = height;
+        self
+    }
+
+    /// Keep the window out of the taskbar and alt-tab list, e.g. for a floating palette.
+    ///
+    /// - On Windows this hides the taskbar button.
+    /// - On X11 this marks the window as a utility window, which most window managers leave out
+    ///   of the taskbar.
+    /// - On other platforms (including Wayland) it does nothing.
+    pub fn with_skip_taskbar(mut self, skip: bool) -> Self {
+        self.config.skip_taskbar = skip;
+        self
+    }
+
+    /// Draw a new frame as soon as a batch of input events has been handled, rather than
+    /// waiting for the event loop to request a redraw.
+    ///
+    /// This saves up to a frame of latency between e.g. a pen going down and ink appearing, at
+    /// the cost of sometimes drawing more frames.
+    ///
+    /// Frames are still held back by soft vsync and the idle frame rate, see
+    /// [`App::with_soft_vsync`] and [`App::with_idle_timeout`].
+    pub fn with_low_latency(mut self, low_latency: bool) -> Self {
+        self.config.low_latency = low_latency;
+        self
+    }
+
+    /// Whether to scale everything drawn by the screen's scale factor (default `true`).
+    ///
+    /// Turn this off if you already work in physical pixels or do your own scaling. Coordinates
+    /// are then physical everywhere: [`Screen::size`] reports the physical size, and cursor
+    /// positions are in physical pixels.
+    pub fn with_auto_dpi_scale(mut self, auto_scale: bool) -> Self {
+        self.config.auto_dpi_scale = auto_scale;
+        self
+    }
+
+    /// Customize the window before it is created, for winit options that snog doesn't wrap.
+    ///
+    /// `f` is called with snog's own settings (size, title, etc.) already applied, so anything it
+    /// sets takes priority.
+    pub fn with_window_builder(
+        mut self,
+        f: impl FnOnce(WindowBuilder) -> WindowBuilder + 'static,
+    ) -> Self {
+        self.hooks.window_builder = Some(Box::new(f));
+        self
+    }
+
+    /// Don't present frames where `render` drew nothing, leaving the previous frame on screen.
+    ///
+    /// This is useful for overlays that only occasionally have content. Note that to clear the
+    /// screen you then need to draw something, e.g. a transparent rectangle.
+    pub fn with_skip_empty_frames(mut self, skip: bool) -> Self {
+        self.config.skip_empty_frames = skip;
+        self
+    }
+
+    /// Whether to draw frames all the time, or only when something happens (default
+    /// [`LoopMode::Continuous`]).
+    pub fn with_loop_mode(mut self, mode: LoopMode) -> Self {
+        self.config.loop_mode = mode;
+        self
+    }
+
+    /// Wait until the window has stopped changing size for `debounce` before resizing the
+    /// drawing surface and drawing again.
+    ///
+    /// On some platforms, dragging the window edge sends a storm of resize events, and
+    /// reconfiguring the surface for each one makes the drag stutter. With this set, the last
+    /// frame is stretched to fit until the size settles. [`Event::Resized`] is still sent for
+    /// every change.
+    pub fn with_resize_debounce(mut self, debounce: Duration) -> Self {
+        self.config.resize_debounce = Some(debounce);
+        self
+    }
+
+    /// Print a warning to stderr for every frame with more than `limit` path segments.
+    ///
+    /// This helps catch scenes that are getting too big for vello before they start glitching.
+    /// See [`RenderCtx::encoded_size`].
+    pub fn with_path_segment_warning(mut self, limit: u32) -> Self {
+        self.config.path_segment_warning = Some(limit);
+        self
+    }
+
+    /// Draw at most `millihertz / 1000` frames a second, waiting between frames rather than
+    /// relying on the present mode to do it. `None` uses the refresh rate of the monitor the
+    /// window is on.
+    ///
+    /// With immediate or mailbox presenting this is a middle ground between tearing and the extra
+    /// latency of fifo: capping the rate a little under the refresh rate means frames rarely
+    /// arrive mid-scan. If the monitor's refresh rate isn't known, frames aren't paced.
+    pub fn with_soft_vsync(mut self, millihertz: Option<u32>) -> Self {
+        self.config.soft_vsync = Some(match millihertz {
+            Some(millihertz) => SoftVsync::Millihertz(millihertz),
+            None => SoftVsync::Monitor,
+        });
+        self
+    }
+
+    /// Only draw with the given graphics APIs, e.g. `wgpu::Backends::DX12` to avoid a buggy
+    /// Vulkan driver.
+    ///
+    /// By default wgpu picks from Vulkan, Metal, DX12 and WebGPU (or what's set in the
+    /// `WGPU_BACKEND` environment variable). If no GPU supports the backends asked for, the app
+    /// prints an error and exits with code 1 when it starts. The limit also applies to the GPU
+    /// devices snog sets up for itself, for rendering offscreen.
+    pub fn with_backends(mut self, backends: wgpu::Backends) -> Self {
+        self.config.backends = Some(backends);
+        self
+    }
+
+    /// Repeat held keys at a steady rate: after `initial`, then every `interval`.
+    ///
+    /// The repeats are sent as extra [`Event::KeyboardInput`] presses, and the platform's own
+    /// repeats are dropped, so holding a key (e.g. an arrow to move through text) behaves the
+    /// same everywhere.
+    pub fn with_key_repeat(mut self, initial: Duration, interval: Duration) -> Self {
+        self.config.key_repeat = Some((initial, interval));
+        self
+    }
+
+    /// The color the scene is drawn over (default black, or transparent for a translucent
+    /// window).
+    ///
+    /// Use [`Color::TRANSPARENT`](peniko::Color::TRANSPARENT) with [`App::render_frames`] to
+    /// export images with a clean alpha channel, e.g. for sprites. The images have straight
+    /// (not premultiplied) alpha.
+    pub fn with_base_color(mut self, color: Color) -> Self {
+        self.config.base_color = Some(color);
+        self
+    }
+
+    /// Force the window decorations (e.g. the title bar on Windows) to a light or dark theme.
+    /// `None`, the default, follows the system theme.
+    ///
+    /// Changes to the theme are reported with [`Event::ThemeChanged`].
+    pub fn with_theme(mut self, theme: Option<Theme>) -> Self {
+        self.config.theme = theme;
+        self
+    }
+
+    /// Save power by drawing at most `idle_fps` frames a second once there has been no mouse or
+    /// keyboard input for `timeout`. The full frame rate comes back with the next input.
+    ///
+    /// Animations started with [`RenderCtx::animate_for`] run at the full frame rate even when
+    /// idle.
+    pub fn with_idle_timeout(mut self, timeout: Duration, idle_fps: u32) -> Self {
+        self.config.idle_timeout = Some((timeout, idle_fps));
+        self
+    }
+
+    /// Print every event the app receives to stderr, with the time since the app started and
+    /// the control flow after handling it, for debugging input problems.
+    ///
+    /// Events are printed after the event filter (see [`App::with_event_filter`]), so this shows
+    /// exactly what [`AppLogic::event`] sees.
+    pub fn with_event_logging(mut self, log: bool) -> Self {
+        self.config.event_logging = log;
+        self
+    }
+
+    /// Adjust the colors of each frame before it's shown, e.g. with [`ToneMapMode::Gamma`] to
+    /// make an app look the same on displays that show it too dark or too light.
+    ///
+    /// This draws each frame into a texture and copies it to the window through a shader, which
+    /// costs a little GPU time. [`AppLogic::post_render`] sees the adjusted frame.
+    pub fn with_tone_mapping(mut self, mode: ToneMapMode) -> Self {
+        self.config.tone_mapping = mode;
+        self
+    }
+
+    /// Send at most `max` events to the app between frames, so that a flood of input (e.g. from
+    /// a replay or a misbehaving device) can't hold up drawing.
+    ///
+    /// Events over the limit aren't dropped: they are kept in order and sent after the next
+    /// frame.
+    pub fn with_max_events_per_frame(mut self, max: usize) -> Self {
+        self.config.max_events_per_frame = Some(max);
+        self
+    }
+
+    /// Call [`AppLogic::fixed_update`] every `timestep`, so the app's simulation runs at the same
+    /// rate whatever the frame rate.
+    ///
+    /// Frames are drawn between updates, so draw a blend of the previous and current states
+    /// using [`RenderCtx::interpolation_alpha`] to keep movement smooth. If the app falls more
+    /// than a few updates behind, the missed time is skipped rather than caught up.
+    pub fn with_fixed_timestep(mut self, timestep: Duration) -> Self {
+        self.config.fixed_timestep = Some(timestep);
+        self
+    }
+
+    /// Draw a small frame offscreen as soon as the renderer is created, and only show the window
+    /// afterwards.
+    ///
+    /// The first frame vello draws is slow, since the GPU driver finishes setting up its
+    /// pipelines and vello allocates its buffers. This moves that work before the window
+    /// appears, so animations don't start with a hitch.
+    pub fn with_pipeline_warmup(mut self, warmup: bool) -> Self {
+        self.config.pipeline_warmup = warmup;
+        self
+    }
+
+    /// How the compositor blends the window with what's behind it.
+    ///
+    /// By default wgpu chooses (usually opaque), until the window is made transparent with
+    /// [`RenderCtx::set_window_opacity`] or a transparent base color, when a mode that blends is
+    /// chosen instead. snog draws straight alpha, so `PostMultiplied` is the right choice where
+    /// it's supported. A mode the surface doesn't support is ignored with a warning. See
+    /// [`RenderCtx::alpha_mode`] for the mode in use.
+    pub fn with_alpha_mode(mut self, mode: wgpu::CompositeAlphaMode) -> Self {
+        self.config.alpha_mode = Some(mode);
+        self
+    }
+
+    /// Let [`App::prefers_reduced_motion`] read the system's setting (default `false`).
+    ///
+    /// On Linux and macOS there's no API for this, so it runs `gsettings` or `defaults` once,
+    /// which is why it's off unless you ask for it.
+    pub fn with_reduced_motion_detection(mut self, detect: bool) -> Self {
+        self.config.reduced_motion_detection = detect;
+        self
+    }
+
+    /// Pass every event through `filter` before it reaches [`AppLogic::event`].
+    ///
+    /// The filter can return the event unchanged, return a different event (e.g. to remap keys),
+    /// or return `None` to drop it.
+    pub fn with_event_filter(
+        mut self,
+        filter: impl FnMut(Event) -> Option<Event> + 'static,
+    ) -> Self {
+        self.hooks.event_filter = Some(Box::new(filter));
+        self
+    }
+
+    /// Write every event the app receives to the file at `path`, with the time it arrived, so
+    /// it can be played back with [`App::replay_events`].
+    ///
+    /// Events are recorded after the event filter (see [`App::with_event_filter`]) has run.
+    #[cfg(feature = "serde")]
+    pub fn record_events(mut self, path: impl AsRef<Path>) -> std::io::Result<Self> {
+        self.hooks.recorder = Some(record::Recorder::create(path.as_ref())?);
+        Ok(self)
+    }
+
+    /// Play back events recorded with [`App::record_events`], at the times they were recorded.
+    ///
+    /// Mouse and keyboard input from the user is ignored until playback has finished, so it
+    /// doesn't interfere with the recording. Frames are drawn after each event is played back,
+    /// even in [`LoopMode::Reactive`].
+    #[cfg(feature = "serde")]
+    pub fn replay_events(mut self, path: impl AsRef<Path>) -> std::io::Result<Self> {
+        self.hooks.replayer = Some(record::Replayer::load(path.as_ref())?);
+        Ok(self)
+    }
+
+    /// Call `f` whenever the loop goes round without drawing a frame, with the reason why.
+    ///
+    /// This is for working out why an app isn't updating. With [`LoopMode::Continuous`] and
+    /// nothing getting in the way, it's never called.
+    pub fn on_render_skipped(mut self, f: impl Fn(SkipReason) + 'static) -> Self {
+        self.hooks.render_skipped = Some(Box::new(f));
+        self
+    }
+
+    /// Call `f` with the time the frame took whenever a frame takes longer than `budget`.
+    ///
+    /// The frame time is measured from when snog starts drawing the frame to when it has been
+    /// presented, so time spent idle between frames (in [`LoopMode::Reactive`], when paced, or
+    /// while the window is hidden) doesn't count. It does include waiting for the GPU to free
+    /// up a surface texture, so a frame that misses vsync shows up. Use a budget a little under
+    /// your monitor's refresh interval (e.g. 15ms for 60Hz).
+    pub fn on_slow_frame(mut self, budget: Duration, f: impl Fn(Duration) + 'static) -> Self {
+        self.hooks.slow_frame = Some((budget, Box::new(f)));
+        self
+    }
+
+    /// The application id, used by the desktop to group windows in the taskbar and find their
+    /// icon.
+    ///
+    /// This sets the Wayland `app_id` and the X11 `WM_CLASS`. It does nothing on other platforms.
+    pub fn with_app_id(mut self, app_id: impl Into<String>) -> Self {
+        self.config.app_id = Some(app_id.into());
+        self
     }
 }
 
 impl<T: AppLogic + 'static> App<T> {
-    pub fn run(mut self) {
-        let event_loop = EventLoop::new();
-        let mut render_cx = RenderContext::new().unwrap();
+    pub fn run(self) {
+        self.run_with_event_loop(EventLoop::new())
+    }
+
+    /// Run the app in a window until `frames` frames have been presented, then exit.
+    ///
+    /// This is for snapshot tests that need a real window and GPU, and saves having to send a
+    /// close event. Like [`App::run`], it exits the process rather than returning.
+    ///
+    /// In [`LoopMode::Reactive`] frames are drawn one after another anyway, since there may be
+    /// no input to wait for, and frames skipped by [`App::with_skip_empty_frames`] still count.
+    pub fn run_frames(mut self, frames: u64) {
+        self.hooks.frame_limit = Some(frames);
+        self.run()
+    }
+
+    /// Like [`App::run_frames`], but also renders the last frame to an image and passes it to
+    /// `on_capture` before exiting.
+    ///
+    /// The capture is drawn offscreen from the same scene, so it doesn't include anything the
+    /// window system draws (like decorations).
+    pub fn run_frames_and_capture(
+        mut self,
+        frames: u64,
+        on_capture: impl FnOnce(RgbaImage) + 'static,
+    ) {
+        self.hooks.capture_last_frame = Some(Box::new(on_capture));
+        self.run_frames(frames)
+    }
+
+    /// Run the app on Android, where winit needs the `AndroidApp` passed to `android_main`.
+    ///
+    /// Android suspends apps when they go into the background, which destroys the drawing
+    /// surface. This is handled for you: the surface is recreated when the app resumes.
+    ///
+    /// winit needs one of its `android-native-activity` or `android-game-activity` features to
+    /// build for Android, and snog doesn't choose for you, so turn on the one matching your
+    /// app's activity in your own `Cargo.toml`:
+    ///
+    /// ```toml
+    /// [target.'cfg(target_os = "android")'.dependencies]
+    /// winit = { version = "0.28.2", features = ["android-native-activity"] }
+    /// ```
+    #[cfg(target_os = "android")]
+    pub fn run_android(self, android_app: AndroidApp) {
+        use winit::{event_loop::EventLoopBuilder, platform::android::EventLoopBuilderExtAndroid};
+        let event_loop = EventLoopBuilder::new()
+            .with_android_app(android_app)
+            .build();
+        self.run_with_event_loop(event_loop)
+    }
+
+    fn run_with_event_loop(mut self, event_loop: EventLoop<()>) {
+        let mut render_cx = render_context(self.config.backends);
+        self.painter.base_color = self.config.base_color;
 
         let mut renderers: Vec<Option<Renderer>> = vec![];
 
         let mut cached_window = None;
-        let mut scene = Scene::new();
-        let mut fragment = SceneFragment::new();
+        self.start = Instant::now();
+        // Set when input arrived in this batch of events, so low latency mode can draw it
+        // without waiting for `RedrawRequested`
+        let mut input_arrived = false;
+        // Set when something happened that might change what's drawn, for reactive mode
+        let mut needs_redraw = true;
+        // When to resize the surface, if we're waiting for resize events to settle
+        let mut resize_at: Option<Instant> = None;
+        // Used to spot displays being connected or disconnected
+        let mut monitors: Vec<MonitorHandle> = vec![];
+        // The monitor the window was last seen on, so moving within it doesn't check again
+        let mut current_monitor: Option<MonitorHandle> = None;
+        // The time between frames with soft vsync, which depends on the monitor we're on
+        let mut frame_interval = None;
+        // For dropping the frame rate when idle
+        let mut last_input = Instant::now();
+        // Whether the window is completely hidden, so there's no point drawing
+        let mut occluded = false;
+        // Used to learn which characters keys type, for `RenderCtx::key_char`
+        let mut modifiers = ModifiersState::empty();
+        let mut pressed_scancode = None;
+        let mut key_repeat = self
+            .config
+            .key_repeat
+            .map(|(initial, interval)| input::KeyRepeat::new(initial, interval));
+        // Events sent to the app since the last frame, and those over the limit waiting for the
+        // next one
+        let max_events = self.config.max_events_per_frame.unwrap_or(usize::MAX);
+        let running_frames = self.hooks.frame_limit.is_some();
+        let mut events_handled = 0;
+        let mut deferred: VecDeque<Event> = VecDeque::new();
 
         let mut render_state: Option<RenderState> = None;
 
         event_loop.run(move |event, event_loop, control_flow| match event {
             WEvent::Resumed => {
                 let Option::None = render_state else { return };
-                let window = cached_window
-                    .take()
-                    .unwrap_or_else(|| create_window(event_loop));
+                let window = cached_window.take().unwrap_or_else(|| {
+                    create_window(event_loop, &self.config, self.hooks.window_builder.take())
+                });
+                monitors = window.available_monitors().collect();
+                frame_interval = soft_vsync_interval(&self.config, &window);
                 let size = window.inner_size();
                 self.screen = Some(Screen {
                     phy_size: Size::new(size.width as f64, size.height as f64),
                     scale_factor: window.scale_factor(),
+                    auto_scale: self.config.auto_dpi_scale,
                 });
                 let surface_future = render_cx.create_surface(&window, size.width, size.height);
                 // We need to block here, in case a Suspended event appeared
                 let Ok(surface) = pollster::block_on(surface_future) else {
+                    match self.config.backends {
+                        Some(backends) => {
+                            eprintln!("Couldn't find a GPU that supports the backends {backends:?}")
+                        }
+                        None => eprintln!("Couldn't find a GPU to draw with"),
+                    }
                     *control_flow = ControlFlow::ExitWithCode(1);
                     return;
                 };
                 render_state = {
-                    let render_state = RenderState { window, surface };
-                    renderers.resize_with(render_cx.devices.len(), || None);
-                    let id = render_state.surface.dev_id;
-                    renderers[id].get_or_insert_with(|| {
-                        Renderer::new(
-                            &render_cx.devices[id].device,
-                            RendererOptions {
-                                surface_format: Some(render_state.surface.format),
-                                timestamp_period: 1.,
-                                use_cpu: false,
-                                antialiasing_support: AaSupport::area_only(),
-                            },
-                        )
-                        .expect("Couldn't create renderer")
-                    });
+                    let tone_mapper = ToneMapper::new(
+                        &render_cx.devices[surface.dev_id].device,
+                        surface.format,
+                        self.config.tone_mapping,
+                    );
+                    let alpha_modes = surface_alpha_modes(&render_cx, &surface);
+                    let mut render_state = RenderState {
+                        window,
+                        surface,
+                        tone_mapper,
+                        alpha_modes,
+                    };
+                    self.update_alpha_mode(&render_cx, &mut render_state);
+                    // Create the renderer now rather than on the first frame, which is slow enough
+                    let renderer = renderer_for(&mut renderers, &render_cx, &render_state.surface);
+                    if self.config.pipeline_warmup {
+                        warm_up(&render_cx.devices[render_state.surface.dev_id], renderer);
+                        // The window was created hidden, so it doesn't show until it can draw
+                        render_state.window.set_visible(true);
+                    }
                     Some(render_state)
                 };
-                *control_flow = ControlFlow::Poll;
+                // The first frame is drawn either way, since `needs_redraw` starts out set
+                *control_flow = match self.config.loop_mode {
+                    LoopMode::Continuous => ControlFlow::Poll,
+                    LoopMode::Reactive => ControlFlow::Wait,
+                };
             }
             WEvent::Suspended => {
                 eprintln!("Suspending");
@@ -261,80 +1622,183 @@ impl<T: AppLogic + 'static> App<T> {
                 }
                 *control_flow = ControlFlow::Wait;
             }
+            WEvent::NewEvents(_) => events_handled = 0,
             WEvent::MainEventsCleared => {
-                if let Some(render_state) = &mut render_state {
+                let mut input_arrived = std::mem::take(&mut input_arrived);
+                let Some(render_state) = &mut render_state else {
+                    return;
+                };
+                if self.frame_limit_reached() {
+                    *control_flow = ControlFlow::ExitWithCode(0);
+                    return;
+                }
+                if std::mem::take(&mut self.painter.state.input.reset_pending) {
+                    if let Some(repeat) = &mut key_repeat {
+                        repeat.clear();
+                    }
+                    pressed_scancode = None;
+                    if !modifiers.is_empty() {
+                        modifiers = ModifiersState::empty();
+                        self.deliver(Event::ModifiersChanged(modifiers), control_flow);
+                        needs_redraw = true;
+                    }
+                }
+                if resize::resize_due(&mut resize_at, Instant::now()) {
+                    let size = render_state.window.inner_size();
+                    render_cx.resize_surface(&mut render_state.surface, size.width, size.height);
+                    needs_redraw = true;
+                }
+                while events_handled < max_events {
+                    let Some(event) = deferred.pop_front() else {
+                        break;
+                    };
+                    events_handled += 1;
+                    if let Some(is_input) = self.dispatch(event, control_flow) {
+                        needs_redraw = true;
+                        if is_input {
+                            last_input = Instant::now();
+                            input_arrived = true;
+                        }
+                    }
+                }
+                #[cfg(feature = "serde")]
+                while events_handled < max_events {
+                    let Some(event) = self
+                        .hooks
+                        .replayer
+                        .as_mut()
+                        .and_then(|replayer| replayer.next_due(self.start.elapsed()))
+                    else {
+                        break;
+                    };
+                    events_handled += 1;
+                    // Recorded events have already been through the filter, so skip it
+                    self.deliver(event, control_flow);
+                    needs_redraw = true;
+                }
+                #[cfg(feature = "serde")]
+                let next_replay = self
+                    .hooks
+                    .replayer
+                    .as_ref()
+                    .and_then(|replayer| replayer.next_time())
+                    .map(|time| self.start + time);
+                #[cfg(not(feature = "serde"))]
+                let next_replay = None;
+                if let Some(repeat) = &mut key_repeat {
+                    while events_handled < max_events {
+                        let Some(event) = repeat.next_due(Instant::now()) else {
+                            break;
+                        };
+                        events_handled += 1;
+                        // Holding a key down counts as input, so we don't go idle
+                        if let Some(is_input) = self.dispatch(event, control_flow) {
+                            needs_redraw = true;
+                            if is_input {
+                                last_input = Instant::now();
+                                input_arrived = true;
+                            }
+                        }
+                    }
+                }
+                let next_repeat = key_repeat.as_ref().and_then(|repeat| repeat.next_time());
+                let animating = match self.painter.state.animate_until {
+                    Some(deadline) if deadline > Instant::now() => true,
+                    Some(_) => {
+                        // Draw one last frame so the animation finishes where it should
+                        self.painter.state.animate_until = None;
+                        needs_redraw = true;
+                        false
+                    }
+                    None => false,
+                };
+                let wants_redraw = match self.config.loop_mode {
+                    LoopMode::Continuous => true,
+                    // `run_frames` has to keep drawing, even without any input
+                    LoopMode::Reactive => {
+                        std::mem::take(&mut needs_redraw) || animating || running_frames
+                    }
+                };
+                // Drop to the idle frame rate when there's been no input for a while, unless
+                // something is animating
+                let idle_interval = self
+                    .config
+                    .idle_timeout
+                    .filter(|(timeout, _)| !animating && last_input.elapsed() >= *timeout)
+                    .map(|(_, fps)| Duration::from_secs_f64(1. / f64::from(fps.max(1))));
+                let interval = frame_interval.max(idle_interval);
+                // With soft vsync or when idle, hold the frame back if it's too soon after the
+                // last one
+                let paced_until = interval
+                    .zip(self.last_frame)
+                    .map(|(interval, last)| last + interval)
+                    .filter(|at| wants_redraw && *at > Instant::now());
+                if !wants_redraw {
+                    self.render_skipped(SkipReason::NothingChanged);
+                } else if occluded {
+                    // Remember to draw once we're visible again
+                    needs_redraw = true;
+                    self.render_skipped(SkipReason::Occluded);
+                } else if paced_until.is_some() {
+                    needs_redraw = true;
+                    self.render_skipped(SkipReason::Paced);
+                } else if input_arrived && self.config.low_latency && resize_at.is_none() {
+                    // Draw once for the whole batch of input, straight away
+                    self.redraw(&render_cx, &mut renderers, render_state);
+                } else {
                     render_state.window.request_redraw();
                 }
+                if !matches!(control_flow, ControlFlow::ExitWithCode(_)) {
+                    match self.config.loop_mode {
+                        // Wait to be told we're visible again, unless there are events to catch
+                        // up on
+                        LoopMode::Continuous if occluded => {
+                            *control_flow = if deferred.is_empty() {
+                                ControlFlow::Wait
+                            } else {
+                                ControlFlow::Poll
+                            };
+                        }
+                        LoopMode::Continuous
+                            if frame_interval.is_some() || self.config.idle_timeout.is_some() =>
+                        {
+                            *control_flow =
+                                paced_until.map_or(ControlFlow::Poll, ControlFlow::WaitUntil);
+                        }
+                        LoopMode::Continuous => (),
+                        LoopMode::Reactive => {
+                            let deadline = resize_at
+                                .into_iter()
+                                .chain(next_replay)
+                                .chain(next_repeat)
+                                .chain(paced_until)
+                                .min();
+                            let busy = animating || running_frames || !deferred.is_empty();
+                            *control_flow = if busy && paced_until.is_none() {
+                                ControlFlow::Poll
+                            } else if let Some(deadline) = deadline {
+                                ControlFlow::WaitUntil(deadline)
+                            } else {
+                                ControlFlow::Wait
+                            };
+                        }
+                    }
+                }
+                self.painter.state.next_wake = match *control_flow {
+                    ControlFlow::WaitUntil(deadline) => Some(deadline),
+                    _ => None,
+                };
             }
             WEvent::RedrawRequested(_) => {
                 let Some(render_state) = &mut render_state else {
                     return;
                 };
-                let width = render_state.surface.config.width;
-                let height = render_state.surface.config.height;
-                let device_handle = &render_cx.devices[render_state.surface.dev_id];
-
-                let mut builder = SceneBuilder::for_fragment(&mut fragment);
-
-                // https://github.com/linebender/vello/issues/291
-                // TODO remove after issue is resolved.
-                {
-                    let brush = vello::peniko::Brush::Solid(Color::BLACK);
-                    builder.fill(
-                        vello::peniko::Fill::NonZero,
-                        Affine::IDENTITY,
-                        &brush,
-                        None,
-                        &vello::kurbo::Rect::new(0., 0., 10., 10.),
-                    );
+                // While waiting for resizing to settle, the last frame gets stretched instead
+                if resize_at.is_some() {
+                    self.render_skipped(SkipReason::ResizePending);
+                    return;
                 }
-                let s = self.screen.unwrap_or(Screen {
-                    phy_size: Size::new(width as f64, height as f64),
-                    scale_factor: 1.,
-                });
-                let mut ctx = RenderCtx {
-                    scene_builder: &mut builder,
-                    screen: s,
-                    text_ctx: &mut self.text,
-                };
-                self.logic.render(&mut ctx);
-
-                // If the user specifies a base color in the CLI we use that. Otherwise we use any
-                // color specified by the scene. The default is black.
-                let render_params = vello::RenderParams {
-                    base_color: Color::BLACK,
-                    width,
-                    height,
-                    antialiasing_method: AaConfig::Area,
-                };
-                let mut builder = SceneBuilder::for_scene(&mut scene);
-                // We apply scaling to the fragment to account for screen scale factor
-                let scale = self.screen.map(|s| {
-                    let s = s.scale_factor;
-                    Affine::scale(s)
-                });
-                builder.append(&fragment, scale);
-                let surface_texture = render_state
-                    .surface
-                    .surface
-                    .get_current_texture()
-                    .expect("failed to get surface texture");
-                vello::block_on_wgpu(
-                    &device_handle.device,
-                    renderers[render_state.surface.dev_id]
-                        .as_mut()
-                        .unwrap()
-                        .render_to_surface_async(
-                            &device_handle.device,
-                            &device_handle.queue,
-                            &scene,
-                            &surface_texture,
-                            &render_params,
-                        ),
-                )
-                .expect("failed to render to surface");
-                surface_texture.present();
-                device_handle.device.poll(wgpu::Maintain::Poll);
+                self.redraw(&render_cx, &mut renderers, render_state);
             }
             WEvent::WindowEvent { event, window_id } => {
                 let Some(render_state) = &mut render_state else {
@@ -344,53 +1808,107 @@ impl<T: AppLogic + 'static> App<T> {
                     return;
                 }
 
+                let recheck_monitors = match &event {
+                    // Moves come in fast while dragging, so only check when we've moved onto
+                    // a different monitor
+                    WindowEvent::Moved(_) => {
+                        let monitor = render_state.window.current_monitor();
+                        monitor != std::mem::replace(&mut current_monitor, monitor.clone())
+                    }
+                    WindowEvent::ScaleFactorChanged { .. } | WindowEvent::Focused(true) => {
+                        current_monitor = render_state.window.current_monitor();
+                        true
+                    }
+                    _ => false,
+                };
+                if recheck_monitors {
+                    let new_monitors: Vec<_> = render_state.window.available_monitors().collect();
+                    frame_interval = soft_vsync_interval(&self.config, &render_state.window);
+                    if new_monitors != monitors {
+                        monitors = new_monitors;
+                        let count = monitors.len();
+                        self.dispatch(Event::DisplaysChanged { count }, control_flow);
+                    }
+                }
+
                 match &event {
-                    WindowEvent::Resized(size) => {
-                        let phy_size = Size::new(size.width as f64, size.height as f64);
-                        if let Some(s) = self.screen.as_mut() {
-                            // We short-circuit here to avoid emitting a resize event when the size
-                            // hasn't changed
-                            if s.phy_size == phy_size {
-                                return;
+                    WindowEvent::Occluded(is_occluded) => occluded = *is_occluded,
+                    WindowEvent::Focused(false) => {
+                        // We won't hear about anything released while we don't have focus
+                        if let Some(repeat) = &mut key_repeat {
+                            repeat.clear();
+                        }
+                        self.painter.state.input.reset();
+                    }
+                    WindowEvent::ModifiersChanged(state) => modifiers = *state,
+                    WindowEvent::KeyboardInput { input, .. } => {
+                        pressed_scancode = (input.state == ElementState::Pressed
+                            && modifiers.is_empty())
+                        .then_some(input.scancode);
+                    }
+                    WindowEvent::ReceivedCharacter(ch) => {
+                        if let Some(scancode) = pressed_scancode.take() {
+                            if !ch.is_control() {
+                                self.painter.state.key_chars.insert(scancode, *ch);
                             }
-                            s.phy_size = phy_size
-                        } else {
-                            self.screen = Some(Screen {
-                                phy_size,
-                                scale_factor: 1.,
-                            })
                         }
-                        render_cx.resize_surface(
-                            &mut render_state.surface,
-                            size.width,
-                            size.height,
+                    }
+                    WindowEvent::Resized(size) => {
+                        let action = resize::resized(
+                            &mut self.screen,
+                            &mut resize_at,
+                            *size,
+                            &self.config,
+                            Instant::now(),
                         );
-                        render_state.window.request_redraw();
+                        match action {
+                            resize::SurfaceResize::Unchanged => return,
+                            resize::SurfaceResize::Debounced => (),
+                            resize::SurfaceResize::Now(width, height) => {
+                                render_cx.resize_surface(&mut render_state.surface, width, height);
+                                render_state.window.request_redraw();
+                            }
+                        }
                     }
                     WindowEvent::ScaleFactorChanged {
                         scale_factor,
                         new_inner_size,
                     } => {
-                        self.screen = Some(Screen {
-                            phy_size: Size::new(
-                                new_inner_size.width as f64,
-                                new_inner_size.height as f64,
-                            ),
-                            scale_factor: *scale_factor,
-                        });
-
-                        render_cx.resize_surface(
-                            &mut render_state.surface,
-                            new_inner_size.width,
-                            new_inner_size.height,
+                        let action = resize::scale_factor_changed(
+                            &mut self.screen,
+                            &mut resize_at,
+                            *scale_factor,
+                            **new_inner_size,
+                            &self.config,
                         );
+                        if let resize::SurfaceResize::Now(width, height) = action {
+                            render_cx.resize_surface(&mut render_state.surface, width, height);
+                            // Redraw straight away, rather than leaving the old frame on screen
+                            // to be stretched at the old scale.
+                            render_state.window.request_redraw();
+                        }
                     }
                     _ => (),
                 }
 
                 if let Some(screen) = self.screen {
-                    if let Some(evt) = Event::from_winit_window(event, screen) {
-                        self.logic.event(evt, control_flow);
+                    let evt = Event::from_winit_window(event, screen, &self.config).filter(|evt| {
+                        // With our own key repeat, the platform's repeats are dropped
+                        key_repeat.as_mut().is_none_or(|repeat| repeat.track(evt))
+                    });
+                    let Some(evt) = evt else { return };
+                    if events_handled >= max_events || !deferred.is_empty() {
+                        // Sent after the next frame, behind the events already waiting
+                        deferred.push_back(evt);
+                        return;
+                    }
+                    events_handled += 1;
+                    if let Some(is_input) = self.dispatch(evt, control_flow) {
+                        needs_redraw = true;
+                        if is_input {
+                            last_input = Instant::now();
+                            input_arrived = true;
+                        }
                     }
                 }
             }
@@ -399,20 +1917,602 @@ impl<T: AppLogic + 'static> App<T> {
     }
 }
 
+impl<T: AppLogic> App<T> {
+    /// Pass `event` on to the app, through the event filter if there is one.
+    ///
+    /// Returns `None` if the filter dropped the event, otherwise whether the event that reached
+    /// the app was input.
+    fn dispatch(&mut self, event: Event, control_flow: &mut ControlFlow) -> Option<bool> {
+        #[cfg(feature = "serde")]
+        if event.is_input()
+            && self
+                .hooks
+                .replayer
+                .as_ref()
+                .is_some_and(|replayer| replayer.next_time().is_some())
+        {
+            // Don't let the user interfere with a replay
+            return None;
+        }
+        let event = match &mut self.hooks.event_filter {
+            Some(filter) => filter(event)?,
+            None => event,
+        };
+        let is_input = event.is_input();
+        self.deliver(event, control_flow);
+        Some(is_input)
+    }
+
+    /// Pass `event` straight to the app, recording it if we're recording.
+    fn deliver(&mut self, event: Event, control_flow: &mut ControlFlow) {
+        #[cfg(feature = "serde")]
+        if let Some(recorder) = &mut self.hooks.recorder {
+            if let Err(e) = recorder.record(self.start.elapsed(), &event) {
+                eprintln!("Couldn't record event, stopping recording: {e}");
+                self.hooks.recorder = None;
+            }
+        }
+        self.painter.state.input.update(&event);
+        let logged = self.config.event_logging.then(|| format!("{event:?}"));
+        self.logic.event(event, control_flow);
+        if let Some(event) = logged {
+            let time = self.start.elapsed().as_secs_f64();
+            eprintln!("[{time:9.3}s] {event} -> {control_flow:?}");
+        }
+    }
+
+    fn render_skipped(&self, reason: SkipReason) {
+        if let Some(f) = &self.hooks.render_skipped {
+            f(reason);
+        }
+    }
+
+    /// Run the fixed updates that are due by the current time, if there's a fixed timestep,
+    /// and note how far it is towards the next one.
+    fn fixed_updates(&mut self) {
+        if let Some(timestep) = self.config.fixed_timestep {
+            self.painter.state.interpolation_alpha = self.fixed_updates_for(timestep);
+        }
+    }
+
+    fn fixed_updates_for(&mut self, timestep: Duration) -> f64 {
+        // A zero timestep would update forever without ever drawing a frame
+        let timestep = timestep.max(Duration::from_micros(100));
+        let time = self.painter.state.time;
+        // Skip ahead rather than spending longer and longer catching up
+        let behind_limit = time.saturating_sub(timestep * MAX_FIXED_UPDATES);
+        self.simulated = self.simulated.max(behind_limit);
+        while self.simulated + timestep <= time {
+            self.logic.fixed_update(timestep);
+            self.simulated += timestep;
+        }
+        (time - self.simulated).as_secs_f64() / timestep.as_secs_f64()
+    }
+
+    /// Configure the surface with the alpha mode asked for, or one that suits whether the
+    /// window is transparent.
+    fn update_alpha_mode(&mut self, render_cx: &RenderContext, render_state: &mut RenderState) {
+        let supported = &render_state.alpha_modes;
+        let mode = match self.config.alpha_mode {
+            Some(mode) if supported.contains(&mode) => mode,
+            Some(mode) => {
+                eprintln!("The surface doesn't support the alpha mode {mode:?}, using the default");
+                self.config.alpha_mode = None;
+                wgpu::CompositeAlphaMode::Auto
+            }
+            None => {
+                let transparent = self.painter.state.opacity < 1.
+                    || self.config.base_color.is_some_and(|color| color.a < 255);
+                let blending = [
+                    wgpu::CompositeAlphaMode::PostMultiplied,
+                    wgpu::CompositeAlphaMode::PreMultiplied,
+                    wgpu::CompositeAlphaMode::Inherit,
+                ];
+                let mode = if transparent {
+                    blending.into_iter().find(|mode| supported.contains(mode))
+                } else {
+                    None
+                };
+                mode.unwrap_or(wgpu::CompositeAlphaMode::Auto)
+            }
+        };
+        let surface = &mut render_state.surface;
+        if surface.config.alpha_mode != mode {
+            surface.config.alpha_mode = mode;
+            // `resize_surface` reuses the config, so this lasts
+            surface
+                .surface
+                .configure(&render_cx.devices[surface.dev_id].device, &surface.config);
+        }
+        self.painter.state.alpha_mode = Some(mode);
+    }
+
+    /// Whether we've drawn all the frames asked for with [`App::run_frames`].
+    fn frame_limit_reached(&self) -> bool {
+        self.hooks
+            .frame_limit
+            .is_some_and(|limit| self.frames_run >= limit)
+    }
+
+    /// Count a frame towards [`App::run_frames`], and capture it if it's the last one.
+    fn frame_done(&mut self, params: &RenderParams) {
+        self.frames_run += 1;
+        if self.frame_limit_reached() {
+            if let Some(on_capture) = self.hooks.capture_last_frame.take() {
+                let mut offscreen = offscreen::Offscreen::new(self.config.backends);
+                on_capture(offscreen.render(&self.painter.scene, params));
+            }
+        }
+    }
+
+    /// Render a frame and present it to the window.
+    fn redraw(
+        &mut self,
+        render_cx: &RenderContext,
+        renderers: &mut Vec<Option<Renderer>>,
+        render_state: &mut RenderState,
+    ) {
+        if self.frame_limit_reached() {
+            return;
+        }
+        let width = render_state.surface.config.width;
+        let height = render_state.surface.config.height;
+        if width == 0 || height == 0 {
+            self.render_skipped(SkipReason::ZeroSize);
+            return;
+        }
+        let device_handle = &render_cx.devices[render_state.surface.dev_id];
+
+        let s = self.screen.unwrap_or(Screen {
+            phy_size: Size::new(width as f64, height as f64),
+            scale_factor: 1.,
+            auto_scale: self.config.auto_dpi_scale,
+        });
+        let now = Instant::now();
+        self.last_frame = Some(now);
+        self.painter.state.time = now - self.start;
+        self.fixed_updates();
+        self.painter.paint(
+            &mut self.logic,
+            s,
+            Some(&render_state.window),
+            &device_handle.device,
+            &device_handle.queue,
+            Some(&render_cx.instance),
+        );
+        if let Some(limit) = self.config.path_segment_warning {
+            let size = self.painter.state.encoded_size;
+            if size.path_segments > limit {
+                eprintln!(
+                    "Frame has {} path segments, more than the limit of {limit}",
+                    size.path_segments
+                );
+            }
+        }
+        let render_params = self.painter.render_params(width, height);
+        if self.config.skip_empty_frames && !self.painter.state.drawn {
+            // Leave the last frame on screen
+            self.render_skipped(SkipReason::EmptyScene);
+            // This still counts as a frame, otherwise `run_frames` would never finish
+            self.frame_done(&render_params);
+            return;
+        }
+        // Painting may have made the window transparent
+        self.update_alpha_mode(render_cx, render_state);
+        let present_time = present(
+            device_handle,
+            renderer_for(renderers, render_cx, &render_state.surface),
+            &self.painter.scene,
+            &render_state.surface,
+            &render_params,
+            render_state.tone_mapper.as_mut(),
+            &mut self.logic,
+        );
+        if let Some((budget, f)) = &self.hooks.slow_frame {
+            let frame_time = present_time - now;
+            if frame_time > *budget {
+                f(frame_time);
+            }
+        }
+        self.logic
+            .on_frame_presented(self.painter.state.frame_index, present_time);
+        self.painter.state.frame_index += 1;
+        self.frame_done(&render_params);
+    }
+
+    /// Render `frames` frames without a window, passing each one to `on_frame` along with its
+    /// index.
+    ///
+    /// The clock returned by [`RenderCtx::time`] starts at zero and advances by exactly
+    /// `timestep` each frame, so the output is the same every time. The window is treated as
+    /// being `width` by `height` pixels with a scale factor of 1.
+    pub fn render_frames(
+        self,
+        width: u32,
+        height: u32,
+        frames: u64,
+        timestep: Duration,
+        mut on_frame: impl FnMut(u64, RgbaImage),
+    ) {
+        self.try_render_frames(width, height, frames, timestep, |idx, image| {
+            on_frame(idx, image);
+            Ok::<_, Infallible>(())
+        })
+        .unwrap_or_else(|never| match never {});
+    }
+
+    /// Render `frames` frames without a window, writing them to `dir` as a numbered PNG sequence
+    /// (`frame_00000.png`, `frame_00001.png`, ...).
+    ///
+    /// See [`App::render_frames`] for how the clock advances.
+    pub fn render_frames_to_dir(
+        self,
+        width: u32,
+        height: u32,
+        frames: u64,
+        timestep: Duration,
+        dir: impl AsRef<Path>,
+    ) -> image::ImageResult<()> {
+        let dir = dir.as_ref();
+        self.try_render_frames(width, height, frames, timestep, |idx, image| {
+            image.save(dir.join(format!("frame_{idx:05}.png")))
+        })
+    }
+
+    /// Render `frames` frames offscreen as fast as possible, and report how long they took.
+    ///
+    /// Each frame time covers calling `render` and waiting for the GPU to finish drawing, but not
+    /// reading the image back. The clock advances by 1/60s each frame, as if running at 60fps.
+    /// The first frame is slower as it includes setting up the GPU pipelines, so use enough
+    /// frames that it doesn't skew the results.
+    ///
+    /// Returns `None` if `frames` is 0, since there's nothing to time.
+    pub fn benchmark(mut self, width: u32, height: u32, frames: u64) -> Option<FrameTimes> {
+        if frames == 0 {
+            return None;
+        }
+        let mut offscreen = offscreen::Offscreen::new(self.config.backends);
+        self.painter.base_color = self.config.base_color;
+        let screen = Screen {
+            phy_size: Size::new(width as f64, height as f64),
+            scale_factor: 1.,
+            auto_scale: true,
+        };
+        let timestep = Duration::from_secs(1) / 60;
+        let mut samples = Vec::with_capacity(frames as usize);
+        for idx in 0..frames {
+            let start = Instant::now();
+            self.painter.state.time = timestep.mul_f64(idx as f64);
+            self.painter.state.frame_index = idx;
+            self.fixed_updates();
+            self.painter.paint(
+                &mut self.logic,
+                screen,
+                None,
+                offscreen.device(),
+                offscreen.queue(),
+                Some(offscreen.instance()),
+            );
+            let params = self.painter.render_params(width, height);
+            offscreen.draw(&self.painter.scene, &params);
+            offscreen.wait();
+            samples.push(start.elapsed());
+        }
+        FrameTimes::from_samples(samples)
+    }
+
+    fn try_render_frames<E>(
+        mut self,
+        width: u32,
+        height: u32,
+        frames: u64,
+        timestep: Duration,
+        mut on_frame: impl FnMut(u64, RgbaImage) -> Result<(), E>,
+    ) -> Result<(), E> {
+        let mut offscreen = offscreen::Offscreen::new(self.config.backends);
+        self.painter.base_color = self.config.base_color;
+        let screen = Screen {
+            phy_size: Size::new(width as f64, height as f64),
+            scale_factor: 1.,
+            auto_scale: true,
+        };
+        let mut time = Duration::ZERO;
+        for idx in 0..frames {
+            self.painter.state.time = time;
+            self.painter.state.frame_index = idx;
+            self.fixed_updates();
+            self.painter.paint(
+                &mut self.logic,
+                screen,
+                None,
+                offscreen.device(),
+                offscreen.queue(),
+                Some(offscreen.instance()),
+            );
+            let params = self.painter.render_params(width, height);
+            on_frame(idx, offscreen.render(&self.painter.scene, &params))?;
+            time += timestep;
+        }
+        Ok(())
+    }
+}
+
+/// A wgpu instance limited to `backends`, or with wgpu's default choice if `None`.
+fn render_context(backends: Option<wgpu::Backends>) -> RenderContext {
+    match backends {
+        Some(backends) => RenderContext {
+            instance: wgpu::Instance::new(wgpu::InstanceDescriptor {
+                backends,
+                ..Default::default()
+            }),
+            devices: vec![],
+        },
+        None => RenderContext::new().unwrap(),
+    }
+}
+
+/// The renderer for the device `surface` is on, created the first time it's needed.
+///
+/// A surface can be on a different device after it's recreated (e.g. when the app resumes on
+/// a laptop that has switched GPU), so this is looked up every frame.
+fn renderer_for<'r>(
+    renderers: &'r mut Vec<Option<Renderer>>,
+    render_cx: &RenderContext,
+    surface: &RenderSurface,
+) -> &'r mut Renderer {
+    let id = surface.dev_id;
+    slot(renderers, id).get_or_insert_with(|| {
+        Renderer::new(
+            &render_cx.devices[id].device,
+            RendererOptions {
+                surface_format: Some(surface.format),
+                timestamp_period: 1.,
+                use_cpu: false,
+                antialiasing_support: AaSupport::area_only(),
+            },
+        )
+        .expect("Couldn't create renderer")
+    })
+}
+
+/// The entry for device `id` in a list indexed by device, growing the list if it's too short.
+///
+/// Device ids come from vello's [`RenderContext`], so they can skip over devices we haven't
+/// needed anything for.
+fn slot<T>(items: &mut Vec<Option<T>>, id: usize) -> &mut Option<T> {
+    if items.len() <= id {
+        items.resize_with(id + 1, || None);
+    }
+    &mut items[id]
+}
+
+/// The alpha modes that `surface` can be configured with.
+fn surface_alpha_modes(
+    render_cx: &RenderContext,
+    surface: &RenderSurface,
+) -> Vec<wgpu::CompositeAlphaMode> {
+    // vello doesn't let us at the adapter it chose, so choose again the same way it does. The
+    // instance is limited to `App::with_backends`, so this is too.
+    let adapter = pollster::block_on(wgpu::util::initialize_adapter_from_env_or_default(
+        &render_cx.instance,
+        Some(&surface.surface),
+    ));
+    adapter
+        .map(|adapter| surface.surface.get_capabilities(&adapter).alpha_modes)
+        .unwrap_or_default()
+}
+
+/// Draw a tiny scene offscreen and wait for it, so the first real frame isn't slowed down by
+/// setting up. See [`App::with_pipeline_warmup`].
+fn warm_up(device_handle: &DeviceHandle, renderer: &mut Renderer) {
+    let (device, queue) = (&device_handle.device, &device_handle.queue);
+    let texture = offscreen::create_texture(device, 16, 16, wgpu::TextureUsages::STORAGE_BINDING);
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let mut scene = Scene::new();
+    SceneBuilder::for_scene(&mut scene).fill(
+        vello::peniko::Fill::NonZero,
+        Affine::IDENTITY,
+        Color::WHITE,
+        None,
+        &Rect::new(2., 2., 14., 14.),
+    );
+    let params = RenderParams {
+        base_color: Color::BLACK,
+        width: 16,
+        height: 16,
+        antialiasing_method: AaConfig::Area,
+    };
+    renderer
+        .render_to_texture(device, queue, &scene, &view, &params)
+        .expect("failed to warm up the renderer");
+    device.poll(wgpu::Maintain::Wait);
+}
+
+/// Render `scene` to `surface` and present it, returning when presenting finished.
+fn present(
+    device_handle: &DeviceHandle,
+    renderer: &mut Renderer,
+    scene: &Scene,
+    surface: &RenderSurface,
+    params: &RenderParams,
+    tone_mapper: Option<&mut ToneMapper>,
+    logic: &mut impl AppLogic,
+) -> Instant {
+    let (device, queue) = (&device_handle.device, &device_handle.queue);
+    let surface_texture = surface
+        .surface
+        .get_current_texture()
+        .expect("failed to get surface texture");
+    let view = surface_texture
+        .texture
+        .create_view(&wgpu::TextureViewDescriptor::default());
+    logic.pre_render(device, queue, &view);
+    match tone_mapper {
+        Some(tone_mapper) => tone_mapper
+            .render(device, queue, renderer, scene, &view, params)
+            .expect("failed to render to surface"),
+        None => vello::block_on_wgpu(
+            &device_handle.device,
+            renderer.render_to_surface_async(
+                &device_handle.device,
+                &device_handle.queue,
+                scene,
+                &surface_texture,
+                params,
+            ),
+        )
+        .expect("failed to render to surface"),
+    }
+    logic.post_render(device, queue, &view);
+    surface_texture.present();
+    let present_time = Instant::now();
+    device_handle.device.poll(wgpu::Maintain::Poll);
+    present_time
+}
+
 // Copied from with_init example (as is a lot of other stuff in this code)
 struct RenderState {
     // SAFETY: We MUST drop the surface before the `window`, so the fields
     // must be in this order
     surface: RenderSurface,
     window: Window,
+    /// Created with the surface, since it depends on the surface's device and format.
+    tone_mapper: Option<ToneMapper>,
+    /// The alpha modes the surface supports.
+    alpha_modes: Vec<wgpu::CompositeAlphaMode>,
+}
+
+/// The time to leave between frames, if soft vsync is on.
+fn soft_vsync_interval(config: &Config, window: &Window) -> Option<Duration> {
+    let millihertz = match config.soft_vsync? {
+        SoftVsync::Monitor => window
+            .current_monitor()
+            .and_then(|monitor| monitor.refresh_rate_millihertz())?,
+        SoftVsync::Millihertz(millihertz) => millihertz,
+    };
+    (millihertz > 0).then(|| Duration::from_secs_f64(1000. / millihertz as f64))
 }
 
-fn create_window(event_loop: &winit::event_loop::EventLoopWindowTarget<()>) -> Window {
-    use winit::{dpi::LogicalSize, window::WindowBuilder};
-    WindowBuilder::new()
+fn create_window(
+    event_loop: &winit::event_loop::EventLoopWindowTarget<()>,
+    config: &Config,
+    customize: Option<Box<dyn FnOnce(WindowBuilder) -> WindowBuilder>>,
+) -> Window {
+    use winit::dpi::LogicalSize;
+    let mut builder = WindowBuilder::new()
         .with_inner_size(LogicalSize::new(1044, 800))
         .with_resizable(true)
         .with_title("Snog")
-        .build(&event_loop)
-        .unwrap()
+        .with_theme(config.theme)
+        // Shown once the pipeline is warmed up
+        .with_visible(!config.pipeline_warmup);
+    #[cfg(target_os = "windows")]
+    {
+        use winit::platform::windows::WindowBuilderExtWindows;
+        builder = builder.with_skip_taskbar(config.skip_taskbar);
+    }
+    #[cfg(target_os = "linux")]
+    if config.skip_taskbar {
+        use winit::platform::x11::{WindowBuilderExtX11, XWindowType};
+        builder = builder.with_x11_window_type(vec![XWindowType::Utility]);
+    }
+    #[cfg(target_os = "linux")]
+    if let Some(app_id) = &config.app_id {
+        // The wayland and x11 versions of this set the same thing, so we only need one.
+        use winit::platform::wayland::WindowBuilderExtWayland;
+        builder = builder.with_name(app_id, app_id);
+    }
+    // This goes last so the user can override anything we set
+    if let Some(customize) = customize {
+        builder = customize(builder);
+    }
+    builder.build(&event_loop).unwrap()
+}
+
+/// The output of `program`, or `None` if it couldn't be run.
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn run_settings_tool(program: &str, args: &[&str]) -> Option<std::process::Output> {
+    std::process::Command::new(program).args(args).output().ok()
+}
+
+/// See [`App::prefers_reduced_motion`].
+#[cfg(target_os = "linux")]
+fn system_prefers_reduced_motion() -> Option<bool> {
+    let output = run_settings_tool(
+        "gsettings",
+        &["get", "org.gnome.desktop.interface", "enable-animations"],
+    )?;
+    if !output.status.success() {
+        return None;
+    }
+    match String::from_utf8_lossy(&output.stdout).trim() {
+        "true" => Some(false),
+        "false" => Some(true),
+        _ => None,
+    }
+}
+
+/// See [`App::prefers_reduced_motion`].
+#[cfg(target_os = "macos")]
+fn system_prefers_reduced_motion() -> Option<bool> {
+    let output = run_settings_tool(
+        "defaults",
+        &["read", "com.apple.universalaccess", "reduceMotion"],
+    )?;
+    if output.status.success() {
+        return Some(String::from_utf8_lossy(&output.stdout).trim() == "1");
+    }
+    // The setting is missing until it has been turned on once
+    String::from_utf8_lossy(&output.stderr)
+        .contains("does not exist")
+        .then_some(false)
+}
+
+/// See [`App::prefers_reduced_motion`].
+#[cfg(windows)]
+fn system_prefers_reduced_motion() -> Option<bool> {
+    use std::ffi::c_void;
+
+    // "Animate controls and elements inside windows"
+    const SPI_GETCLIENTAREAANIMATION: u32 = 0x1042;
+    #[link(name = "user32")]
+    extern "system" {
+        fn SystemParametersInfoW(action: u32, param: u32, value: *mut c_void, win_ini: u32) -> i32;
+    }
+    // A Win32 `BOOL`
+    let mut animate: i32 = 0;
+    // SAFETY: this action writes a `BOOL` to `value`, and reads nothing
+    let ok = unsafe {
+        SystemParametersInfoW(
+            SPI_GETCLIENTAREAANIMATION,
+            0,
+            (&mut animate as *mut i32).cast(),
+            0,
+        )
+    };
+    (ok != 0).then_some(animate == 0)
+}
+
+/// See [`App::prefers_reduced_motion`].
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn system_prefers_reduced_motion() -> Option<bool> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::slot;
+
+    #[test]
+    fn slot_grows_for_sparse_ids() {
+        let mut items: Vec<Option<&str>> = vec![];
+        *slot(&mut items, 2) = Some("third");
+        assert_eq!(items, [None, None, Some("third")]);
+        // Earlier ids fit without growing, and don't disturb later ones
+        slot(&mut items, 0).get_or_insert("first");
+        assert_eq!(items, [Some("first"), None, Some("third")]);
+        assert_eq!(slot(&mut items, 2).get_or_insert("other"), &"third");
+        assert_eq!(slot(&mut items, 4), &None);
+        assert_eq!(items.len(), 5);
+    }
 }
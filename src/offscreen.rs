@@ -0,0 +1,431 @@
+//! Rendering to images rather than to a window.
+use std::{
+    sync::mpsc,
+    time::{Duration, Instant},
+};
+
+use image::RgbaImage;
+use vello::{
+    kurbo::Size, peniko::Color, util::RenderContext, AaSupport, RenderParams, Renderer,
+    RendererOptions, Scene,
+};
+
+use crate::{AppLogic, LoopState, Painter, Screen};
+
+/// Renders many small images, e.g. thumbnails for a gallery, reusing the same GPU device and
+/// target texture for each one.
+///
+/// Creating one of these is slow, but rendering with it is fast.
+pub struct ThumbnailRenderer {
+    offscreen: Offscreen,
+    painter: Painter,
+}
+
+impl ThumbnailRenderer {
+    pub fn new() -> Self {
+        Self {
+            offscreen: Offscreen::new(None),
+            painter: Painter::new(),
+        }
+    }
+
+    /// Draw thumbnails over `color` rather than black. Use `Color::TRANSPARENT` to keep the
+    /// alpha channel clean.
+    pub fn set_base_color(&mut self, color: Color) {
+        self.painter.base_color = Some(color);
+    }
+
+    /// Render `logic` into a `width` by `height` image, with a scale factor of 1.
+    ///
+    /// The target texture is only recreated when the size changes, so it's fastest to render
+    /// thumbnails of the same size one after another.
+    pub fn render(&mut self, logic: &mut impl AppLogic, width: u32, height: u32) -> RgbaImage {
+        // Don't let one thumbnail's settings leak into the next
+        self.painter.state = LoopState::new();
+        let screen = Screen {
+            phy_size: Size::new(width as f64, height as f64),
+            scale_factor: 1.,
+            auto_scale: true,
+        };
+        self.painter.paint(
+            logic,
+            screen,
+            None,
+            self.offscreen.device(),
+            self.offscreen.queue(),
+            Some(self.offscreen.instance()),
+        );
+        let params = self.painter.render_params(width, height);
+        self.offscreen.render(&self.painter.scene, &params)
+    }
+}
+
+impl Default for ThumbnailRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Renders into a `wgpu::Texture` on a device you own, e.g. to show snog drawing as a panel
+/// inside an egui or imgui app.
+///
+/// Unlike [`ThumbnailRenderer`], nothing is copied back to the CPU. Use the device (and queue)
+/// that your UI draws with, so that it can sample the texture.
+pub struct TextureRenderer {
+    renderer: Renderer,
+    painter: Painter,
+    target: Option<(wgpu::Texture, wgpu::TextureView)>,
+    start: Instant,
+}
+
+impl TextureRenderer {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let renderer = Renderer::new(
+            device,
+            RendererOptions {
+                surface_format: None,
+                timestamp_period: 1.,
+                use_cpu: false,
+                antialiasing_support: AaSupport::area_only(),
+            },
+        )
+        .expect("Couldn't create renderer");
+        Self {
+            renderer,
+            painter: Painter::new(),
+            target: None,
+            start: Instant::now(),
+        }
+    }
+
+    /// Draw over `color` rather than black, e.g. `Color::TRANSPARENT` to blend the texture
+    /// into your UI.
+    pub fn set_base_color(&mut self, color: Color) {
+        self.painter.base_color = Some(color);
+    }
+
+    /// Render `logic` into a `width` by `height` texture, with a scale factor of 1.
+    ///
+    /// The texture is `Rgba8Unorm` with straight (not premultiplied) alpha, and can be bound
+    /// for sampling. It is only recreated when the size changes, so a texture id registered
+    /// with your UI stays valid until then.
+    pub fn render(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        logic: &mut impl AppLogic,
+        width: u32,
+        height: u32,
+    ) -> &wgpu::Texture {
+        let screen = Screen {
+            phy_size: Size::new(width as f64, height as f64),
+            scale_factor: 1.,
+            auto_scale: true,
+        };
+        // Like in a window, the clock starts when this is created
+        self.painter.state.time = self.start.elapsed();
+        self.painter.paint(logic, screen, None, device, queue, None);
+        self.painter.state.frame_index += 1;
+
+        let target = match self.target.take() {
+            Some(target) if target.0.width() == width && target.0.height() == height => target,
+            _ => {
+                let texture = create_texture(
+                    device,
+                    width,
+                    height,
+                    wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::TEXTURE_BINDING,
+                );
+                let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+                (texture, view)
+            }
+        };
+        let (texture, view) = self.target.insert(target);
+        let params = self.painter.render_params(width, height);
+        self.renderer
+            .render_to_texture(device, queue, &self.painter.scene, view, &params)
+            .expect("failed to render to texture");
+        texture
+    }
+}
+
+/// Frame time percentiles, from [`App::benchmark`](crate::App::benchmark).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameTimes {
+    pub p50: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+}
+
+impl FrameTimes {
+    /// The percentiles of `samples`, or `None` if there aren't any.
+    pub(crate) fn from_samples(mut samples: Vec<Duration>) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        samples.sort_unstable();
+        let percentile = |p: f64| samples[((samples.len() - 1) as f64 * p).round() as usize];
+        Some(Self {
+            p50: percentile(0.5),
+            p95: percentile(0.95),
+            p99: percentile(0.99),
+        })
+    }
+}
+
+/// A GPU device and renderer that draw into a texture, and read the result back.
+pub(crate) struct Offscreen {
+    render_cx: RenderContext,
+    dev_id: usize,
+    readback: Readback,
+}
+
+impl Offscreen {
+    /// Set up a GPU device with one of `backends`, or any if `None`. See
+    /// [`App::with_backends`](crate::App::with_backends).
+    pub(crate) fn new(backends: Option<wgpu::Backends>) -> Self {
+        let mut render_cx = crate::render_context(backends);
+        let dev_id =
+            pollster::block_on(render_cx.device(None)).expect("couldn't find a GPU device");
+        let readback = Readback::new(&render_cx.devices[dev_id].device);
+        Self {
+            render_cx,
+            dev_id,
+            readback,
+        }
+    }
+
+    /// Render `scene` into the target texture, without reading it back.
+    pub(crate) fn draw(&mut self, scene: &Scene, params: &RenderParams) {
+        let device_handle = &self.render_cx.devices[self.dev_id];
+        let (device, queue) = (&device_handle.device, &device_handle.queue);
+        self.readback.draw(device, queue, scene, params);
+    }
+
+    pub(crate) fn device(&self) -> &wgpu::Device {
+        &self.render_cx.devices[self.dev_id].device
+    }
+
+    pub(crate) fn queue(&self) -> &wgpu::Queue {
+        &self.render_cx.devices[self.dev_id].queue
+    }
+
+    pub(crate) fn instance(&self) -> &wgpu::Instance {
+        &self.render_cx.instance
+    }
+
+    /// Block until the GPU has finished all the work it has been given.
+    pub(crate) fn wait(&self) {
+        self.device().poll(wgpu::Maintain::Wait);
+    }
+
+    /// Render `scene` and copy it back to the CPU.
+    ///
+    /// This blocks until the GPU has finished.
+    pub(crate) fn render(&mut self, scene: &Scene, params: &RenderParams) -> RgbaImage {
+        let device_handle = &self.render_cx.devices[self.dev_id];
+        let (device, queue) = (&device_handle.device, &device_handle.queue);
+        self.readback.render(device, queue, scene, params)
+    }
+}
+
+/// A renderer that draws into a texture and reads the result back, on a device that belongs
+/// to someone else, e.g. the window's.
+pub(crate) struct Readback {
+    renderer: Renderer,
+    /// Reused between renders, as long as the size doesn't change.
+    target: Option<Target>,
+}
+
+struct Target {
+    width: u32,
+    height: u32,
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    buffer: wgpu::Buffer,
+    padded_bytes_per_row: u32,
+}
+
+impl Readback {
+    /// A renderer for `device`, which must be the device passed to the other methods.
+    pub(crate) fn new(device: &wgpu::Device) -> Self {
+        let renderer = Renderer::new(
+            device,
+            RendererOptions {
+                surface_format: None,
+                timestamp_period: 1.,
+                use_cpu: false,
+                antialiasing_support: AaSupport::area_only(),
+            },
+        )
+        .expect("Couldn't create renderer");
+        Self {
+            renderer,
+            target: None,
+        }
+    }
+
+    /// Render `scene` into the target texture, without reading it back.
+    pub(crate) fn draw(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        scene: &Scene,
+        params: &RenderParams,
+    ) {
+        let (width, height) = (params.width, params.height);
+        let target = match self.target.take() {
+            Some(target) if target.width == width && target.height == height => target,
+            _ => Target::new(device, width, height),
+        };
+        let target = self.target.insert(target);
+
+        self.renderer
+            .render_to_texture(device, queue, scene, &target.view, params)
+            .expect("failed to render to texture");
+    }
+
+    /// Render `scene` and copy it back to the CPU.
+    ///
+    /// This blocks until the GPU has finished.
+    pub(crate) fn render(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        scene: &Scene,
+        params: &RenderParams,
+    ) -> RgbaImage {
+        self.draw(device, queue, scene, params);
+        let (width, height) = (params.width, params.height);
+        let target = self.target.as_ref().unwrap();
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("snog readback"),
+        });
+        encoder.copy_texture_to_buffer(
+            target.texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &target.buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(target.padded_bytes_per_row),
+                    rows_per_image: None,
+                },
+            },
+            target.extent(),
+        );
+        queue.submit([encoder.finish()]);
+
+        let slice = target.buffer.slice(..);
+        let (tx, rx) = mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |res| {
+            let _ = tx.send(res);
+        });
+        device.poll(wgpu::Maintain::Wait);
+        rx.recv()
+            .unwrap()
+            .expect("failed to read back rendered image");
+
+        let row_len = width as usize * 4;
+        let mut pixels = Vec::with_capacity(row_len * height as usize);
+        {
+            let data = slice.get_mapped_range();
+            for row in data.chunks(target.padded_bytes_per_row as usize) {
+                pixels.extend_from_slice(&row[..row_len]);
+            }
+        }
+        target.buffer.unmap();
+        RgbaImage::from_raw(width, height, pixels).unwrap()
+    }
+}
+
+impl Target {
+    fn new(device: &wgpu::Device, width: u32, height: u32) -> Self {
+        // Rows in the readback buffer must be aligned
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_bytes_per_row = (width * 4).div_ceil(align) * align;
+        let texture = create_texture(
+            device,
+            width,
+            height,
+            wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::COPY_SRC,
+        );
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("snog readback buffer"),
+            size: padded_bytes_per_row as u64 * height as u64,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        Self {
+            width,
+            height,
+            texture,
+            view,
+            buffer,
+            padded_bytes_per_row,
+        }
+    }
+
+    fn extent(&self) -> wgpu::Extent3d {
+        wgpu::Extent3d {
+            width: self.width,
+            height: self.height,
+            depth_or_array_layers: 1,
+        }
+    }
+}
+
+/// A texture that vello can render into.
+pub(crate) fn create_texture(
+    device: &wgpu::Device,
+    width: u32,
+    height: u32,
+    usage: wgpu::TextureUsages,
+) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("snog offscreen target"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        // vello writes straight (not premultiplied) alpha in this format
+        format: wgpu::TextureFormat::Rgba8Unorm,
+        usage,
+        view_formats: &[],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn frame_time_percentiles() {
+        // Given out of order, to check they're sorted first
+        let samples = (1..=100).rev().map(ms).collect();
+        let times = FrameTimes::from_samples(samples).unwrap();
+        assert_eq!(times.p50, ms(51));
+        assert_eq!(times.p95, ms(95));
+        assert_eq!(times.p99, ms(99));
+    }
+
+    #[test]
+    fn frame_time_percentiles_of_one_sample() {
+        let times = FrameTimes::from_samples(vec![ms(7)]).unwrap();
+        assert_eq!((times.p50, times.p95, times.p99), (ms(7), ms(7), ms(7)));
+    }
+
+    #[test]
+    fn no_frame_times() {
+        assert_eq!(FrameTimes::from_samples(vec![]), None);
+    }
+}
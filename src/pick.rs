@@ -0,0 +1,236 @@
+//! Hit testing by drawing objects' ids into an offscreen image, which is exact for any shape,
+//! including thin strokes.
+use std::fmt;
+
+use image::RgbaImage;
+use vello::{
+    kurbo::{Affine, BezPath, Point, Shape, Stroke},
+    peniko::{Color, Fill},
+    AaConfig, RenderParams, Scene, SceneBuilder,
+};
+
+use crate::{offscreen::Readback, RenderCtx, Screen};
+
+/// Identifies something drawn with [`RenderCtx::pick_fill`] or [`RenderCtx::pick_stroke`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ObjectId(pub u32);
+
+struct PickObject {
+    id: ObjectId,
+    transform: Affine,
+    path: BezPath,
+    /// `None` for a fill.
+    stroke: Option<Stroke>,
+}
+
+#[derive(Default)]
+pub(crate) struct PickState {
+    /// Objects added this frame.
+    current: Vec<PickObject>,
+    /// Objects from the last frame, which is what the user can see.
+    last: Vec<PickObject>,
+    /// The ids of `last`, drawn at this scale. Only drawn when needed.
+    image: Option<(f64, RgbaImage)>,
+    /// Created the first time something is picked, for the device we're drawing with.
+    readback: Option<(wgpu::Id<wgpu::Device>, Readback)>,
+}
+
+impl fmt::Debug for PickState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PickState")
+            .field("current", &self.current.len())
+            .field("last", &self.last.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl PickState {
+    /// Start collecting objects for a new frame.
+    pub(crate) fn begin_frame(&mut self) {
+        self.last = std::mem::take(&mut self.current);
+        self.image = None;
+    }
+
+    fn render(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        width: u32,
+        height: u32,
+        scale: f64,
+    ) -> &RgbaImage {
+        let stale = self.image.as_ref().is_some_and(|(image_scale, image)| {
+            *image_scale != scale || image.width() != width || image.height() != height
+        });
+        if stale {
+            self.image = None;
+        }
+        let Self {
+            last,
+            image,
+            readback,
+            ..
+        } = self;
+        let (_, image) = image.get_or_insert_with(|| {
+            let mut scene = Scene::new();
+            let mut builder = SceneBuilder::for_scene(&mut scene);
+            for (idx, object) in last.iter().enumerate() {
+                let color = id_color(idx);
+                let transform = Affine::scale(scale) * object.transform;
+                match &object.stroke {
+                    Some(stroke) => builder.stroke(stroke, transform, color, None, &object.path),
+                    None => builder.fill(Fill::NonZero, transform, color, None, &object.path),
+                }
+            }
+            let params = RenderParams {
+                base_color: Color::TRANSPARENT,
+                width,
+                height,
+                antialiasing_method: AaConfig::Area,
+            };
+            // The window can move to another GPU, and the renderer only works with one
+            let readback = match readback.take() {
+                Some((id, readback)) if id == device.global_id() => readback,
+                _ => Readback::new(device),
+            };
+            let (_, readback) = readback.insert((device.global_id(), readback));
+            let image = readback.render(device, queue, &scene, &params);
+            (scale, image)
+        });
+        image
+    }
+
+    fn pick(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        pos: Point,
+        screen: Screen,
+    ) -> Option<ObjectId> {
+        if self.last.is_empty() {
+            return None;
+        }
+        let width = screen.phy_size.width as u32;
+        let height = screen.phy_size.height as u32;
+        let scale = screen.drawing_scale();
+        let (x, y) = ((pos.x * scale).floor(), (pos.y * scale).floor());
+        if x < 0. || y < 0. || x >= width as f64 || y >= height as f64 {
+            return None;
+        }
+        let image = self.render(device, queue, width, height, scale);
+        let idx = id_at(image, x as u32, y as u32)?;
+        self.last.get(idx).map(|object| object.id)
+    }
+}
+
+/// The index of the object drawn at `(x, y)` in the pick image, if we can be sure of it.
+///
+/// Antialiased edges blend with whatever is behind them, and a blend of two ids is often
+/// opaque and decodes to some other id. So we only trust a pixel whose neighbours (within the
+/// image) are all the same, which is never true on an edge.
+fn id_at(image: &RgbaImage, x: u32, y: u32) -> Option<usize> {
+    let pixel = *image.get_pixel(x, y);
+    let [r, g, b, a] = pixel.0;
+    if a != 255 {
+        return None;
+    }
+    let xs = x.saturating_sub(1)..=(x + 1).min(image.width() - 1);
+    for ny in y.saturating_sub(1)..=(y + 1).min(image.height() - 1) {
+        for nx in xs.clone() {
+            if *image.get_pixel(nx, ny) != pixel {
+                return None;
+            }
+        }
+    }
+    (u32::from_le_bytes([r, g, b, 0]) as usize).checked_sub(1)
+}
+
+/// The color that object `idx` is drawn with in the pick image. 0 means nothing.
+fn id_color(idx: usize) -> Color {
+    let [r, g, b, _] = (idx as u32 + 1).to_le_bytes();
+    Color::rgb8(r, g, b)
+}
+
+impl<'a> RenderCtx<'a> {
+    /// Make the inside of `shape` pickable as `id` with [`RenderCtx::pick_at`].
+    ///
+    /// This doesn't draw anything you can see, so draw the shape as well. Objects added later
+    /// are picked in preference to earlier ones, like drawing. `transform` is applied inside the
+    /// current transform (see [`RenderCtx::set_transform`]) and any [`RenderCtx::region`].
+    pub fn pick_fill(&mut self, id: ObjectId, transform: Affine, shape: &impl Shape) {
+        let transform = self.state.stack.window_transform() * transform;
+        self.state.pick.current.push(PickObject {
+            id,
+            transform,
+            path: shape.to_path(0.1),
+            stroke: None,
+        });
+    }
+
+    /// Make `shape` stroked with `stroke` pickable as `id` with [`RenderCtx::pick_at`].
+    ///
+    /// Use a wider stroke than you draw with to make thin lines easier to hit.
+    pub fn pick_stroke(
+        &mut self,
+        id: ObjectId,
+        stroke: &Stroke,
+        transform: Affine,
+        shape: &impl Shape,
+    ) {
+        let transform = self.state.stack.window_transform() * transform;
+        self.state.pick.current.push(PickObject {
+            id,
+            transform,
+            path: shape.to_path(0.1),
+            stroke: Some(stroke.clone()),
+        });
+    }
+
+    /// The object under `pos`, out of those made pickable during the last frame (the one on
+    /// screen).
+    ///
+    /// The first call after each frame renders the ids offscreen and reads them back, which
+    /// takes a few milliseconds, so call this when you need it (e.g. after a click) rather than
+    /// every frame. Objects are picked exactly, but never within a physical pixel of their
+    /// edges, where antialiasing blends them with what's behind.
+    pub fn pick_at(&mut self, pos: Point) -> Option<ObjectId> {
+        let (device, queue) = (self.device, self.queue);
+        self.state.pick.pick(device, queue, pos, self.screen)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgba;
+
+    use super::*;
+
+    fn id_pixel(idx: usize) -> Rgba<u8> {
+        let color = id_color(idx);
+        Rgba([color.r, color.g, color.b, color.a])
+    }
+
+    #[test]
+    fn picks_only_away_from_edges() {
+        // Object 0 on the left, object 1 on the right, and a blend of the two between them
+        let mut image = RgbaImage::from_pixel(9, 3, id_pixel(0));
+        for y in 0..3 {
+            image.put_pixel(4, y, Rgba([1, 1, 0, 255]));
+            for x in 5..9 {
+                image.put_pixel(x, y, id_pixel(1));
+            }
+        }
+        assert_eq!(id_at(&image, 1, 1), Some(0));
+        // Including at the edge of the image
+        assert_eq!(id_at(&image, 8, 0), Some(1));
+        for x in 3..=5 {
+            assert_eq!(id_at(&image, x, 1), None);
+        }
+    }
+
+    #[test]
+    fn nothing_picked_where_nothing_was_drawn() {
+        let image = RgbaImage::new(3, 3);
+        assert_eq!(id_at(&image, 1, 1), None);
+    }
+}
@@ -0,0 +1,70 @@
+//! Recording events to a file and playing them back, for reproducing bugs.
+//!
+//! The file has one JSON object per line, holding the event and the time it happened since the
+//! app started.
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{self, BufRead, BufReader, LineWriter, Write},
+    path::Path,
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::Event;
+
+#[derive(Serialize, Deserialize)]
+struct Record<E> {
+    time: Duration,
+    event: E,
+}
+
+pub(crate) struct Recorder {
+    // Flushed after every line, because winit exits the process without running destructors.
+    out: LineWriter<File>,
+}
+
+impl Recorder {
+    pub(crate) fn create(path: &Path) -> io::Result<Self> {
+        Ok(Self {
+            out: LineWriter::new(File::create(path)?),
+        })
+    }
+
+    pub(crate) fn record(&mut self, time: Duration, event: &Event) -> io::Result<()> {
+        serde_json::to_writer(&mut self.out, &Record { time, event })?;
+        self.out.write_all(b"\n")
+    }
+}
+
+pub(crate) struct Replayer {
+    records: VecDeque<Record<Event>>,
+}
+
+impl Replayer {
+    pub(crate) fn load(path: &Path) -> io::Result<Self> {
+        let mut records = VecDeque::new();
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            records.push_back(serde_json::from_str(&line)?);
+        }
+        Ok(Self { records })
+    }
+
+    /// The next event that was recorded at or before `now`.
+    pub(crate) fn next_due(&mut self, now: Duration) -> Option<Event> {
+        if self.records.front()?.time > now {
+            return None;
+        }
+        self.records.pop_front().map(|record| record.event)
+    }
+
+    /// When the next event is due, or `None` when everything has been played back.
+    pub(crate) fn next_time(&self) -> Option<Duration> {
+        self.records.front().map(|record| record.time)
+    }
+}
@@ -0,0 +1,205 @@
+//! Keeping the screen size, scale factor and surface size in step as the window changes, so a
+//! frame is never drawn at one scale into a surface sized for another.
+use std::time::Instant;
+
+use vello::kurbo::Size;
+use winit::dpi::PhysicalSize;
+
+use crate::{Config, Screen};
+
+/// What to do with the surface after the window has changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SurfaceResize {
+    /// The size hasn't changed, so there's nothing to do, or to tell the app.
+    Unchanged,
+    /// Resize the surface once resizing settles, see
+    /// [`App::with_resize_debounce`](crate::App::with_resize_debounce).
+    Debounced,
+    /// Resize the surface to this size, and redraw straight away.
+    Now(u32, u32),
+}
+
+/// Update `screen` for a `Resized` event.
+pub(crate) fn resized(
+    screen: &mut Option<Screen>,
+    resize_at: &mut Option<Instant>,
+    size: PhysicalSize<u32>,
+    config: &Config,
+    now: Instant,
+) -> SurfaceResize {
+    let phy_size = Size::new(size.width as f64, size.height as f64);
+    match screen {
+        // We short-circuit here to avoid emitting a resize event when the size hasn't changed
+        Some(s) if s.phy_size == phy_size => return SurfaceResize::Unchanged,
+        Some(s) => s.phy_size = phy_size,
+        None => {
+            *screen = Some(Screen {
+                phy_size,
+                scale_factor: 1.,
+                auto_scale: config.auto_dpi_scale,
+            })
+        }
+    }
+    match config.resize_debounce {
+        Some(debounce) => {
+            *resize_at = Some(now + debounce);
+            SurfaceResize::Debounced
+        }
+        None => SurfaceResize::Now(size.width, size.height),
+    }
+}
+
+/// Update `screen` for a `ScaleFactorChanged` event.
+///
+/// The scale factor and size must change together, otherwise we'd draw a frame at the wrong
+/// scale, so this always resizes straight away. Any pending debounced resize is covered by
+/// this, and if the OS adjusts the size again afterwards, the `Resized` that follows handles it.
+pub(crate) fn scale_factor_changed(
+    screen: &mut Option<Screen>,
+    resize_at: &mut Option<Instant>,
+    scale_factor: f64,
+    size: PhysicalSize<u32>,
+    config: &Config,
+) -> SurfaceResize {
+    *screen = Some(Screen {
+        phy_size: Size::new(size.width as f64, size.height as f64),
+        scale_factor,
+        auto_scale: config.auto_dpi_scale,
+    });
+    *resize_at = None;
+    SurfaceResize::Now(size.width, size.height)
+}
+
+/// Whether a debounced resize is due by `now`, in which case it's no longer pending.
+pub(crate) fn resize_due(resize_at: &mut Option<Instant>, now: Instant) -> bool {
+    let due = resize_at.is_some_and(|deadline| deadline <= now);
+    if due {
+        *resize_at = None;
+    }
+    due
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    /// The state the event loop keeps, and the surface it resizes.
+    struct Window {
+        config: Config,
+        screen: Option<Screen>,
+        resize_at: Option<Instant>,
+        surface: (u32, u32),
+        now: Instant,
+    }
+
+    impl Window {
+        fn new(config: Config) -> Self {
+            Self {
+                config,
+                screen: Some(Screen {
+                    phy_size: Size::new(800., 600.),
+                    scale_factor: 1.,
+                    auto_scale: true,
+                }),
+                resize_at: None,
+                surface: (800, 600),
+                now: Instant::now(),
+            }
+        }
+
+        fn resized(&mut self, width: u32, height: u32) -> SurfaceResize {
+            let size = PhysicalSize::new(width, height);
+            let action = resized(
+                &mut self.screen,
+                &mut self.resize_at,
+                size,
+                &self.config,
+                self.now,
+            );
+            self.apply(action);
+            action
+        }
+
+        fn scale_factor_changed(&mut self, scale_factor: f64, width: u32, height: u32) {
+            let size = PhysicalSize::new(width, height);
+            let action = scale_factor_changed(
+                &mut self.screen,
+                &mut self.resize_at,
+                scale_factor,
+                size,
+                &self.config,
+            );
+            self.apply(action);
+        }
+
+        /// Let `time` pass, applying a debounced resize if it comes due.
+        fn wait(&mut self, time: Duration) -> bool {
+            self.now += time;
+            let due = resize_due(&mut self.resize_at, self.now);
+            if due {
+                let size = self.screen.unwrap().phy_size;
+                self.surface = (size.width as u32, size.height as u32);
+            }
+            due
+        }
+
+        fn apply(&mut self, action: SurfaceResize) {
+            if let SurfaceResize::Now(width, height) = action {
+                self.surface = (width, height);
+            }
+        }
+
+        /// Check that a frame drawn now would be right, if one would be drawn at all.
+        fn assert_frame(&self, scale_factor: f64) {
+            // Frames aren't drawn while a resize is pending
+            if self.resize_at.is_some() {
+                return;
+            }
+            let screen = self.screen.unwrap();
+            assert_eq!(screen.scale_factor, scale_factor);
+            let size = Size::new(self.surface.0 as f64, self.surface.1 as f64);
+            assert_eq!(screen.phy_size, size);
+        }
+    }
+
+    #[test]
+    fn scale_change_then_resize() {
+        let mut window = Window::new(Config::default());
+        window.scale_factor_changed(2., 1600, 1200);
+        window.assert_frame(2.);
+        // The OS echoes the new size, which changes nothing
+        assert_eq!(window.resized(1600, 1200), SurfaceResize::Unchanged);
+        window.assert_frame(2.);
+        assert_eq!(window.resized(1700, 1200), SurfaceResize::Now(1700, 1200));
+        window.assert_frame(2.);
+    }
+
+    #[test]
+    fn scale_change_during_debounce() {
+        let config = Config {
+            resize_debounce: Some(Duration::from_millis(100)),
+            ..Config::default()
+        };
+        let mut window = Window::new(config);
+        assert_eq!(window.resized(900, 600), SurfaceResize::Debounced);
+        assert!(window.resize_at.is_some());
+        window.assert_frame(1.);
+
+        // The scale change applies straight away and replaces the pending resize
+        window.scale_factor_changed(2., 1800, 1200);
+        assert!(window.resize_at.is_none());
+        window.assert_frame(2.);
+        assert!(!window.wait(Duration::from_millis(200)));
+        window.assert_frame(2.);
+
+        assert_eq!(window.resized(1800, 1200), SurfaceResize::Unchanged);
+        assert_eq!(window.resized(2000, 1200), SurfaceResize::Debounced);
+        assert!(!window.wait(Duration::from_millis(50)));
+        window.assert_frame(2.);
+        assert!(window.wait(Duration::from_millis(50)));
+        assert_eq!(window.surface, (2000, 1200));
+        window.assert_frame(2.);
+    }
+}
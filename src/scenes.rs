@@ -0,0 +1,199 @@
+//! Switching between several "screens" of an app, like a menu, the game, and a pause screen.
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    time::{Duration, Instant},
+};
+
+use vello::kurbo::Affine;
+
+use crate::{AppLogic, ControlFlow, Event, RenderCtx, SceneBuilder, SceneFragment};
+
+/// What to do after a scene has handled an event or drawn a frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SceneCommand<K> {
+    /// Keep this scene active.
+    Stay,
+    /// Make the scene registered as `K` active.
+    Switch(K),
+}
+
+/// One screen of an app managed by [`Scenes`]. This is like [`AppLogic`], except that
+/// `render` and `event` say which scene should come next.
+///
+/// The other methods are only called while the scene is active, and default to doing what
+/// [`AppLogic`]'s do.
+pub trait AppScene<K> {
+    fn render<'a>(&'a mut self, cx: &'a mut RenderCtx<'a>) -> SceneCommand<K>;
+    fn event(&mut self, event: Event, cf: &mut ControlFlow) -> SceneCommand<K> {
+        if matches!(event, Event::CloseRequested) {
+            *cf = ControlFlow::Exit;
+        }
+        SceneCommand::Stay
+    }
+
+    /// See [`AppLogic::fixed_update`].
+    fn fixed_update(&mut self, timestep: Duration) {
+        let _ = timestep;
+    }
+
+    /// See [`AppLogic::compose`].
+    fn compose(&mut self, builder: &mut SceneBuilder, fragment: &SceneFragment, scale: Affine) {
+        builder.append(fragment, Some(scale));
+    }
+
+    /// See [`AppLogic::on_frame_presented`].
+    fn on_frame_presented(&mut self, frame_index: u64, present_time: Instant) {
+        let _ = (frame_index, present_time);
+    }
+
+    /// See [`AppLogic::pre_render`].
+    fn pre_render(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        target: &wgpu::TextureView,
+    ) {
+        let _ = (device, queue, target);
+    }
+
+    /// See [`AppLogic::post_render`].
+    fn post_render(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        target: &wgpu::TextureView,
+    ) {
+        let _ = (device, queue, target);
+    }
+}
+
+/// An [`AppLogic`] that passes events and drawing on to whichever of its scenes is active.
+///
+/// ```no_run
+/// # use snog::{App, AppScene, RenderCtx, SceneCommand, Scenes};
+/// # struct Menu;
+/// # struct Game;
+/// # impl AppScene<&'static str> for Menu {
+/// #     fn render<'a>(&'a mut self, _: &'a mut RenderCtx<'a>) -> SceneCommand<&'static str> {
+/// #         SceneCommand::Switch("game")
+/// #     }
+/// # }
+/// # impl AppScene<&'static str> for Game {
+/// #     fn render<'a>(&'a mut self, _: &'a mut RenderCtx<'a>) -> SceneCommand<&'static str> {
+/// #         SceneCommand::Stay
+/// #     }
+/// # }
+/// let scenes = Scenes::new("menu").with("menu", Menu).with("game", Game);
+/// App::new_with_data(scenes).run();
+/// ```
+pub struct Scenes<K> {
+    scenes: HashMap<K, Box<dyn AppScene<K>>>,
+    active: K,
+    /// A switch asked for by `render`, which can't be applied until the frame is done.
+    pending: Option<K>,
+}
+
+impl<K: Hash + Eq> Scenes<K> {
+    /// Start with the scene registered as `initial`, which must be added with [`Scenes::with`]
+    /// before the app runs.
+    pub fn new(initial: K) -> Self {
+        Self {
+            scenes: HashMap::new(),
+            active: initial,
+            pending: None,
+        }
+    }
+
+    /// Register `scene` as `key`, replacing any scene already registered with that key.
+    pub fn with(mut self, key: K, scene: impl AppScene<K> + 'static) -> Self {
+        self.scenes.insert(key, Box::new(scene));
+        self
+    }
+
+    /// The key of the active scene.
+    pub fn active(&self) -> &K {
+        self.pending.as_ref().unwrap_or(&self.active)
+    }
+
+    fn switch_to(&mut self, key: K) {
+        assert!(
+            self.scenes.contains_key(&key),
+            "tried to switch to a scene that wasn't registered"
+        );
+        self.active = key;
+    }
+
+    fn apply_pending(&mut self) {
+        if let Some(key) = self.pending.take() {
+            self.switch_to(key);
+        }
+    }
+
+    fn active_scene(&mut self) -> &mut (dyn AppScene<K> + 'static) {
+        self.scenes
+            .get_mut(&self.active)
+            .expect("the active scene wasn't registered")
+            .as_mut()
+    }
+}
+
+impl<K: Hash + Eq> AppLogic for Scenes<K> {
+    /// Draws the active scene. If it switches scene, the new one is drawn from the next frame.
+    fn render<'a>(&'a mut self, cx: &'a mut RenderCtx<'a>) {
+        self.apply_pending();
+        // The scene stays borrowed for as long as `cx`, so the switch has to wait
+        let Self {
+            scenes,
+            active,
+            pending,
+        } = self;
+        let scene = scenes
+            .get_mut(active)
+            .expect("the active scene wasn't registered");
+        if let SceneCommand::Switch(key) = scene.render(cx) {
+            *pending = Some(key);
+        }
+    }
+
+    fn event(&mut self, event: Event, cf: &mut ControlFlow) {
+        self.apply_pending();
+        if let SceneCommand::Switch(key) = self.active_scene().event(event, cf) {
+            self.switch_to(key);
+        }
+    }
+
+    fn fixed_update(&mut self, timestep: Duration) {
+        self.apply_pending();
+        self.active_scene().fixed_update(timestep);
+    }
+
+    // The rest belong to the frame the active scene just drew, so a switch it asked for waits
+
+    fn compose(&mut self, builder: &mut SceneBuilder, fragment: &SceneFragment, scale: Affine) {
+        self.active_scene().compose(builder, fragment, scale);
+    }
+
+    fn on_frame_presented(&mut self, frame_index: u64, present_time: Instant) {
+        self.active_scene()
+            .on_frame_presented(frame_index, present_time);
+    }
+
+    fn pre_render(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        target: &wgpu::TextureView,
+    ) {
+        self.active_scene().pre_render(device, queue, target);
+    }
+
+    fn post_render(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        target: &wgpu::TextureView,
+    ) {
+        self.active_scene().post_render(device, queue, target);
+    }
+}
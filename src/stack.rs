@@ -0,0 +1,150 @@
+//! Saving and restoring the current transform and clip, like `save` and `restore` on an HTML
+//! canvas.
+use vello::{
+    kurbo::{Affine, Point, Shape},
+    peniko::Mix,
+};
+
+use crate::RenderCtx;
+
+#[derive(Debug)]
+pub(crate) struct DrawStack {
+    transform: Affine,
+    /// The number of clip layers pushed this frame.
+    clips: usize,
+    /// The transform and number of clips at each [`RenderCtx::save`].
+    saves: Vec<(Affine, usize)>,
+    /// From the coordinates `transform` starts from to the window's, which only differ inside
+    /// a [`RenderCtx::region`].
+    origin: Affine,
+}
+
+impl Default for DrawStack {
+    fn default() -> Self {
+        Self {
+            transform: Affine::IDENTITY,
+            clips: 0,
+            saves: vec![],
+            origin: Affine::IDENTITY,
+        }
+    }
+}
+
+impl DrawStack {
+    /// Start drawing in a region whose top-left is at `origin` in the current transform, and
+    /// return the stack to go back to afterwards.
+    pub(crate) fn enter_region(&mut self, origin: Point) -> DrawStack {
+        let inner = DrawStack {
+            origin: self.window_transform() * Affine::translate(origin.to_vec2()),
+            ..DrawStack::default()
+        };
+        std::mem::replace(self, inner)
+    }
+
+    /// From the current drawing coordinates to the window's.
+    pub(crate) fn window_transform(&self) -> Affine {
+        self.origin * self.transform
+    }
+
+    /// `pos` in the window, in the current drawing coordinates, or `None` if the current
+    /// transform can't be undone.
+    pub(crate) fn to_local(&self, pos: Point) -> Option<Point> {
+        let transform = self.window_transform();
+        if transform.determinant() == 0. {
+            return None;
+        }
+        Some(transform.inverse() * pos)
+    }
+
+    /// Check that the frame (or [`RenderCtx::region`]) just drawn restored everything it saved,
+    /// and start afresh.
+    ///
+    /// Returns the number of clips still open, which need closing before the frame is composed.
+    pub(crate) fn end_frame(&mut self) -> usize {
+        let open_clips = self.clips;
+        if !self.saves.is_empty() {
+            mismatch(&format!(
+                "{} `RenderCtx::save`s weren't restored by the end of the frame",
+                self.saves.len()
+            ));
+        }
+        if self.clips > 0 {
+            mismatch(&format!(
+                "{} `RenderCtx::clip`s were still open at the end of the frame, call \
+                 `RenderCtx::save` before clipping and `RenderCtx::restore` after",
+                self.clips
+            ));
+        }
+        *self = Self::default();
+        open_clips
+    }
+}
+
+/// Panic in debug builds, and warn otherwise, since drawing can usually carry on.
+fn mismatch(msg: &str) {
+    if cfg!(debug_assertions) {
+        panic!("{msg}");
+    } else {
+        eprintln!("{msg}");
+    }
+}
+
+impl<'a> RenderCtx<'a> {
+    /// The transform set with [`RenderCtx::set_transform`] and
+    /// [`RenderCtx::apply_transform`], which starts each frame as the identity.
+    ///
+    /// snog's own drawing methods, like [`RenderCtx::draw_text`], [`RenderCtx::label`],
+    /// [`RenderCtx::polyline`] and the widgets, draw with it, and those that take a transform
+    /// apply it inside this one. vello's methods, like
+    /// [`SceneBuilder::fill`](vello::SceneBuilder::fill), use the transform they're given, so
+    /// pass this to them. Store it in a variable first, since
+    /// `cx.fill(.., cx.current_transform(), ..)` borrows `cx` twice.
+    pub fn current_transform(&self) -> Affine {
+        self.state.stack.transform
+    }
+
+    /// Replace the current transform, until the next [`RenderCtx::restore`].
+    pub fn set_transform(&mut self, transform: Affine) {
+        self.state.stack.transform = transform;
+    }
+
+    /// Apply `transform` to everything drawn with the current transform, inside the transforms
+    /// already applied, until the next [`RenderCtx::restore`].
+    pub fn apply_transform(&mut self, transform: Affine) {
+        self.state.stack.transform *= transform;
+    }
+
+    /// Clip everything drawn from now on to `shape`, in the current transform, until the next
+    /// [`RenderCtx::restore`].
+    ///
+    /// vello needs every clip to be closed in the same frame, so call [`RenderCtx::save`]
+    /// before clipping.
+    pub fn clip(&mut self, shape: &impl Shape) {
+        let transform = self.state.stack.transform;
+        self.push_layer(Mix::Clip, 1., transform, shape);
+        self.state.stack.clips += 1;
+    }
+
+    /// Remember the current transform and clip, to go back to with [`RenderCtx::restore`].
+    ///
+    /// Unlike a closure, nothing ties the two calls together, so each `save` must be matched by
+    /// a `restore` in the same frame. A mismatch panics in debug builds. In release builds it
+    /// prints a warning, and any clips left open are closed at the end of the frame.
+    pub fn save(&mut self) {
+        let stack = &mut self.state.stack;
+        stack.saves.push((stack.transform, stack.clips));
+    }
+
+    /// Go back to the transform and clip from the last [`RenderCtx::save`].
+    pub fn restore(&mut self) {
+        let Some((transform, clips)) = self.state.stack.saves.pop() else {
+            mismatch("`RenderCtx::restore` called without a matching `RenderCtx::save`");
+            return;
+        };
+        for _ in clips..self.state.stack.clips {
+            self.pop_layer();
+        }
+        self.state.stack.transform = transform;
+        self.state.stack.clips = clips;
+    }
+}
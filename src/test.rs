@@ -0,0 +1,100 @@
+//! Helpers for visual regression tests.
+use image::{Rgba, RgbaImage};
+
+use crate::{AppLogic, ThumbnailRenderer};
+
+/// Render `a` and `b` offscreen at `width` by `height`, and compare them with [`diff_images`].
+pub fn diff_scenes(
+    a: &mut impl AppLogic,
+    b: &mut impl AppLogic,
+    (width, height): (u32, u32),
+    tolerance: u8,
+) -> (RgbaImage, f64) {
+    let mut renderer = ThumbnailRenderer::new();
+    let a = renderer.render(a, width, height);
+    let b = renderer.render(b, width, height);
+    diff_images(&a, &b, tolerance)
+}
+
+/// Compare two images of the same size pixel by pixel.
+///
+/// Pixels differ if any channel differs by more than `tolerance`, which allows for GPUs that
+/// round slightly differently (1 or 2 is usually enough). Returns an image with differing
+/// pixels in magenta over a faded copy of `a`, and the fraction of pixels that differ, from 0
+/// (identical) to 1.
+///
+/// # Panics
+///
+/// Panics if the images are different sizes.
+pub fn diff_images(a: &RgbaImage, b: &RgbaImage, tolerance: u8) -> (RgbaImage, f64) {
+    assert_eq!(
+        a.dimensions(),
+        b.dimensions(),
+        "can only diff images of the same size"
+    );
+    let mut differing = 0u64;
+    let diff = RgbaImage::from_fn(a.width(), a.height(), |x, y| {
+        let (pa, pb) = (a.get_pixel(x, y), b.get_pixel(x, y));
+        let differs =
+            pa.0.iter()
+                .zip(pb.0)
+                .any(|(ca, cb)| ca.abs_diff(cb) > tolerance);
+        if differs {
+            differing += 1;
+            Rgba([255, 0, 255, 255])
+        } else {
+            let [r, g, bl, _] = pa.0;
+            let fade = |c: u8| 192 + c / 4;
+            Rgba([fade(r), fade(g), fade(bl), 255])
+        }
+    });
+    let total = u64::from(a.width()) * u64::from(a.height());
+    let fraction = if total == 0 {
+        0.
+    } else {
+        differing as f64 / total as f64
+    };
+    (diff, fraction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_images() {
+        let image = RgbaImage::from_pixel(4, 4, Rgba([10, 20, 30, 255]));
+        let (diff, fraction) = diff_images(&image, &image, 0);
+        assert_eq!(fraction, 0.);
+        // A faded copy of the image
+        assert_eq!(*diff.get_pixel(0, 0), Rgba([194, 197, 199, 255]));
+    }
+
+    #[test]
+    fn differences_beyond_tolerance() {
+        let a = RgbaImage::from_pixel(2, 2, Rgba([100, 100, 100, 255]));
+        let mut b = a.clone();
+        b.put_pixel(0, 0, Rgba([102, 100, 100, 255]));
+        b.put_pixel(1, 1, Rgba([100, 100, 100, 252]));
+
+        let (diff, fraction) = diff_images(&a, &b, 2);
+        assert_eq!(fraction, 0.25);
+        assert_eq!(*diff.get_pixel(0, 0), Rgba([217, 217, 217, 255]));
+        assert_eq!(*diff.get_pixel(1, 1), Rgba([255, 0, 255, 255]));
+
+        let (_, fraction) = diff_images(&a, &b, 0);
+        assert_eq!(fraction, 0.5);
+    }
+
+    #[test]
+    fn empty_images() {
+        let (_, fraction) = diff_images(&RgbaImage::new(0, 0), &RgbaImage::new(0, 0), 0);
+        assert_eq!(fraction, 0.);
+    }
+
+    #[test]
+    #[should_panic(expected = "same size")]
+    fn different_sizes() {
+        diff_images(&RgbaImage::new(2, 1), &RgbaImage::new(1, 2), 0);
+    }
+}
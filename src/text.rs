@@ -5,7 +5,7 @@ use vello::{
         fello::{raw::FontRef, MetadataProvider},
         Glyph, GlyphContext,
     },
-    kurbo::Affine,
+    kurbo::{Affine, Size},
     peniko::{Blob, Brush, BrushRef, Font, StyleRef},
     SceneBuilder,
 };
@@ -13,6 +13,19 @@ use vello::{
 // Copied from vello example
 const INCONSOLATA_FONT: &[u8] = include_bytes!("../assets/inconsolata/Inconsolata.ttf");
 
+/// Extra space between letters and lines, for
+/// [`RenderCtx::draw_text_spaced`](crate::RenderCtx::draw_text_spaced).
+///
+/// The default is the font's natural spacing.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct TextSpacing {
+    /// Added after each character, in the same units as the font size. Negative values pull
+    /// letters closer together.
+    pub letter_spacing: f32,
+    /// The distance between baselines, or `None` for the font's own line height.
+    pub line_height: Option<f32>,
+}
+
 pub struct SimpleText {
     gcx: GlyphContext,
     inconsolata: Font,
@@ -109,11 +122,13 @@ impl SimpleText {
             );
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn add(
         &mut self,
         builder: &mut SceneBuilder,
         font: Option<&Font>,
         size: f32,
+        spacing: TextSpacing,
         brush: Option<&Brush>,
         transform: Affine,
         text: &str,
@@ -123,7 +138,9 @@ impl SimpleText {
         let fello_size = vello::fello::Size::new(size);
         let charmap = font.charmap();
         let metrics = font.metrics(fello_size, Default::default());
-        let line_height = metrics.ascent - metrics.descent + metrics.leading;
+        let line_height = spacing
+            .line_height
+            .unwrap_or(metrics.ascent - metrics.descent + metrics.leading);
         let glyph_metrics = font.glyph_metrics(fello_size, Default::default());
         let mut pen_x = 0f64;
         let mut pen_y = 0f64;
@@ -143,8 +160,57 @@ impl SimpleText {
                     * Affine::scale_non_uniform(1.0, -1.0);
                 builder.append(&glyph, Some(xform));
             }
-            pen_x += advance;
+            pen_x += advance + f64::from(spacing.letter_spacing);
+        }
+    }
+}
+
+impl SimpleText {
+    /// The distance between the baselines of lines of text.
+    pub fn line_height(&self, font: Option<&Font>, size: f32) -> f32 {
+        let default_font = FontRef::new(INCONSOLATA_FONT).unwrap();
+        let font = font.and_then(to_font_ref).unwrap_or(default_font);
+        let metrics = font.metrics(vello::fello::Size::new(size), Default::default());
+        metrics.ascent - metrics.descent + metrics.leading
+    }
+
+    /// The size of the box that [`SimpleText::add`] draws `text` in, where the top of the box is
+    /// `size` above the first baseline.
+    pub fn measure(
+        &self,
+        font: Option<&Font>,
+        size: f32,
+        spacing: TextSpacing,
+        text: &str,
+    ) -> Size {
+        let default_font = FontRef::new(INCONSOLATA_FONT).unwrap();
+        let font = font.and_then(to_font_ref).unwrap_or(default_font);
+        let fello_size = vello::fello::Size::new(size);
+        let charmap = font.charmap();
+        let metrics = font.metrics(fello_size, Default::default());
+        let line_height = spacing
+            .line_height
+            .unwrap_or(metrics.ascent - metrics.descent + metrics.leading);
+        let glyph_metrics = font.glyph_metrics(fello_size, Default::default());
+        let mut width = 0f32;
+        let mut lines = 0;
+        for line in text.split('\n') {
+            let advances: f32 = line
+                .chars()
+                .map(|ch| {
+                    let gid = charmap.map(ch).unwrap_or_default();
+                    glyph_metrics.advance_width(gid).unwrap_or_default()
+                })
+                .sum();
+            // The spacing after the last character isn't part of the line
+            let gaps = line.chars().count().saturating_sub(1) as f32;
+            let line_width = advances + gaps * spacing.letter_spacing;
+            width = width.max(line_width);
+            lines += 1;
         }
+        // `descent` is negative, as it's below the baseline
+        let height = size + (lines - 1) as f32 * line_height - metrics.descent;
+        Size::new(width.into(), height.into())
     }
 }
 
@@ -0,0 +1,68 @@
+//! Short messages like "Saved!" that fade out on their own.
+use std::time::Duration;
+
+use vello::{kurbo::Point, peniko::Color};
+
+use crate::RenderCtx;
+
+/// How long a toast takes to fade out, at most.
+const FADE: Duration = Duration::from_millis(300);
+const FONT_SIZE: f32 = 16.;
+const PADDING: f64 = 8.;
+/// The gap between the toast and the bottom of the window.
+const MARGIN: f64 = 32.;
+
+#[derive(Debug)]
+pub(crate) struct Toast {
+    text: String,
+    /// The time it goes away, on the clock from [`RenderCtx::time`].
+    until: Duration,
+    fade: Duration,
+}
+
+impl<'a> RenderCtx<'a> {
+    /// Show `text` at the bottom of the window for `duration`, then fade it out.
+    ///
+    /// This is for short messages like "Saved!" or "Copied to clipboard". The toast is drawn
+    /// over everything else, and frames keep being drawn while it's visible. Showing another
+    /// toast replaces this one.
+    pub fn toast(&mut self, text: impl Into<String>, duration: Duration) {
+        self.state.toast = Some(Toast {
+            text: text.into(),
+            until: self.state.time + duration,
+            fade: FADE.min(duration / 4),
+        });
+        self.animate_for(duration);
+    }
+
+    /// Draw the toast, if it's still visible.
+    pub(crate) fn draw_toast(&mut self) {
+        let Some(toast) = self.state.toast.take() else {
+            return;
+        };
+        let Some(remaining) = toast.until.checked_sub(self.state.time) else {
+            return;
+        };
+        let alpha = if remaining < toast.fade {
+            remaining.as_secs_f64() / toast.fade.as_secs_f64()
+        } else {
+            1.
+        };
+
+        let screen = self.screen.size();
+        let size = self.measure_text(FONT_SIZE, &toast.text);
+        let origin = Point::new(
+            (screen.width - size.width) * 0.5,
+            screen.height - MARGIN - PADDING - size.height,
+        );
+        self.label(
+            &toast.text,
+            origin,
+            FONT_SIZE,
+            Color::WHITE.with_alpha_factor(alpha as f32),
+            Color::rgba8(0x20, 0x20, 0x20, 0xe0).with_alpha_factor(alpha as f32),
+            PADDING,
+        );
+        self.state.toast = Some(toast);
+    }
+}
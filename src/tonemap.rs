@@ -0,0 +1,172 @@
+//! A last pass over each frame that adjusts its colors before it's presented.
+use vello::{RenderParams, Renderer, Scene};
+
+use crate::offscreen::create_texture;
+
+/// How colors are adjusted before being shown, see
+/// [`App::with_tone_mapping`](crate::App::with_tone_mapping).
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ToneMapMode {
+    /// Show colors exactly as they are drawn.
+    None,
+    /// Raise each color channel to the power `1 / gamma`. Values above 1 brighten the midtones
+    /// and values below 1 darken them, without changing black, white or alpha.
+    Gamma(f32),
+}
+
+/// Draws the scene into a texture of its own, then copies it to the surface through a shader
+/// that applies the tone mapping.
+pub(crate) struct ToneMapper {
+    layout: wgpu::BindGroupLayout,
+    pipeline: wgpu::RenderPipeline,
+    /// Reused between frames, as long as the size doesn't change.
+    target: Option<(wgpu::Texture, wgpu::TextureView, wgpu::BindGroup)>,
+}
+
+impl ToneMapper {
+    /// A tone mapper that draws to surfaces of `format`, or `None` if there's nothing to do.
+    pub(crate) fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        mode: ToneMapMode,
+    ) -> Option<Self> {
+        let body = match mode {
+            ToneMapMode::None => return None,
+            ToneMapMode::Gamma(gamma) => format!(
+                "return vec4<f32>(pow(color.rgb, vec3<f32>({:?})), color.a);",
+                1. / gamma.max(0.01)
+            ),
+        };
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("snog tone mapping"),
+            source: wgpu::ShaderSource::Wgsl(SHADER.replace("BODY", &body).into()),
+        });
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("snog tone mapping"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            }],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("snog tone mapping"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("snog tone mapping"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &module,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &module,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+        });
+        Some(Self {
+            layout,
+            pipeline,
+            target: None,
+        })
+    }
+
+    /// Render `scene` to `view`, which is `params.width` by `params.height`.
+    pub(crate) fn render(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        renderer: &mut Renderer,
+        scene: &Scene,
+        view: &wgpu::TextureView,
+        params: &RenderParams,
+    ) -> vello::Result<()> {
+        let target = match self.target.take() {
+            Some(target)
+                if target.0.width() == params.width && target.0.height() == params.height =>
+            {
+                target
+            }
+            _ => {
+                let texture = create_texture(
+                    device,
+                    params.width,
+                    params.height,
+                    wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::TEXTURE_BINDING,
+                );
+                let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+                let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("snog tone mapping"),
+                    layout: &self.layout,
+                    entries: &[wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&view),
+                    }],
+                });
+                (texture, view, bind_group)
+            }
+        };
+        let (_, target_view, bind_group) = self.target.insert(target);
+        renderer.render_to_texture(device, queue, scene, target_view, params)?;
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("snog tone mapping"),
+        });
+        {
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("snog tone mapping"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, bind_group, &[]);
+            pass.draw(0..3, 0..1);
+        }
+        queue.submit(Some(encoder.finish()));
+        Ok(())
+    }
+}
+
+/// Draws one triangle that covers the screen, copying the frame across pixel for pixel. `BODY`
+/// is replaced with code that returns the adjusted `color`.
+const SHADER: &str = "
+@group(0) @binding(0) var frame: texture_2d<f32>;
+
+@vertex
+fn vs_main(@builtin(vertex_index) idx: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((idx << 1u) & 2u), f32(idx & 2u));
+    return vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+}
+
+@fragment
+fn fs_main(@builtin(position) pos: vec4<f32>) -> @location(0) vec4<f32> {
+    let color = textureLoad(frame, vec2<i32>(pos.xy), 0);
+    BODY
+}
+";
@@ -0,0 +1,148 @@
+//! A tiny set of immediate-mode widgets, for small control panels that don't need a GUI
+//! library.
+//!
+//! Each widget is drawn and handles input in one call during `render`, and returns whether the
+//! user changed its value. For text, use [`RenderCtx::label`]. Widgets are drawn with, and
+//! respond to the mouse through, the current transform (see [`RenderCtx::set_transform`]).
+use std::ops::RangeInclusive;
+
+use vello::{
+    kurbo::{Affine, Circle, Point, Rect, RoundedRect, Stroke},
+    peniko::{Color, Fill},
+};
+
+use crate::{draw::path_through, stack::DrawStack, MouseButton, RenderCtx};
+
+const BACKGROUND: Color = Color::rgb8(0xf4, 0xf4, 0xf4);
+const BORDER: Color = Color::rgb8(0x76, 0x76, 0x76);
+const ACCENT: Color = Color::rgb8(0x1a, 0x73, 0xe8);
+const TRACK: Color = Color::rgb8(0xc8, 0xc8, 0xc8);
+const CORNER_RADIUS: f64 = 3.;
+
+impl<'a> RenderCtx<'a> {
+    /// Draw a checkbox filling `rect`, and toggle `value` when it's clicked.
+    ///
+    /// Returns whether `value` changed.
+    pub fn checkbox(&mut self, rect: Rect, value: &mut bool) -> bool {
+        let rect = rect.abs();
+        let changed = self.was_mouse_pressed(MouseButton::Left) && self.is_hovered(rect);
+        if changed {
+            *value = !*value;
+        }
+
+        let transform = self.current_transform();
+        let shape = RoundedRect::from_rect(rect, CORNER_RADIUS);
+        if *value {
+            self.fill(Fill::NonZero, transform, ACCENT, None, &shape);
+            let at = |x: f64, y: f64| {
+                Point::new(rect.x0 + x * rect.width(), rect.y0 + y * rect.height())
+            };
+            let tick = path_through(&[at(0.22, 0.52), at(0.42, 0.72), at(0.78, 0.3)], false);
+            let stroke = Stroke::new(rect.height() * 0.12);
+            self.stroke(&stroke, transform, Color::WHITE, None, &tick);
+        } else {
+            self.fill(Fill::NonZero, transform, BACKGROUND, None, &shape);
+            self.stroke(&Stroke::new(1.), transform, BORDER, None, &shape);
+        }
+        self.add_local_bounds(rect);
+        changed
+    }
+
+    /// Draw a horizontal slider filling `rect`, and set `value` (somewhere in `range`) while
+    /// it's dragged.
+    ///
+    /// Returns whether `value` changed.
+    pub fn slider(&mut self, rect: Rect, value: &mut f64, range: RangeInclusive<f64>) -> bool {
+        let rect = rect.abs();
+        let (min, max) = (*range.start(), *range.end());
+        let knob_radius = rect.height() * 0.5;
+        // The knob's centre stays inside the slider, so it doesn't stick out at the ends
+        let (left, right) = (rect.x0 + knob_radius, rect.x1 - knob_radius);
+
+        if self.was_mouse_pressed(MouseButton::Left) && self.is_hovered(rect) {
+            self.state.input.dragging = Some(rect);
+        }
+        let mut changed = false;
+        if self.state.input.dragging == Some(rect) {
+            if let Some(pos) = self.local_cursor() {
+                let t = if right > left {
+                    ((pos.x - left) / (right - left)).clamp(0., 1.)
+                } else {
+                    0.
+                };
+                let new_value = min + t * (max - min);
+                if new_value != *value {
+                    *value = new_value;
+                    changed = true;
+                }
+            }
+        }
+
+        let t = if max != min {
+            ((*value - min) / (max - min)).clamp(0., 1.)
+        } else {
+            0.
+        };
+        let knob_x = left + t * (right - left);
+        let y = rect.center().y;
+        let track_height = (rect.height() * 0.2).max(2.);
+        let track = |x0: f64, x1: f64| {
+            RoundedRect::new(
+                x0,
+                y - track_height * 0.5,
+                x1,
+                y + track_height * 0.5,
+                track_height * 0.5,
+            )
+        };
+        let transform = self.current_transform();
+        let full = track(left, right);
+        self.fill(Fill::NonZero, transform, TRACK, None, &full);
+        let filled = track(left, knob_x);
+        self.fill(Fill::NonZero, transform, ACCENT, None, &filled);
+        let knob = Circle::new((knob_x, y), knob_radius);
+        self.fill(Fill::NonZero, transform, BACKGROUND, None, &knob);
+        self.stroke(&Stroke::new(1.), transform, BORDER, None, &knob);
+        self.add_local_bounds(rect);
+        changed
+    }
+
+    fn is_hovered(&self, rect: Rect) -> bool {
+        hovered(&self.state.stack, self.cursor_position(), rect)
+    }
+
+    /// The cursor position in the coordinates we're drawing in, taking the current transform
+    /// and any [`RenderCtx::region`] into account, so widgets respond where they appear.
+    fn local_cursor(&self) -> Option<Point> {
+        self.state.stack.to_local(self.cursor_position()?)
+    }
+}
+
+/// Whether `cursor`, in window coordinates, is over `rect` drawn with `stack`.
+fn hovered(stack: &DrawStack, cursor: Option<Point>, rect: Rect) -> bool {
+    cursor
+        .and_then(|pos| stack.to_local(pos))
+        .is_some_and(|pos| rect.contains(pos))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checkbox_in_region() {
+        let checkbox = Rect::new(10., 10., 30., 30.);
+        let mut stack = DrawStack::default();
+        assert!(hovered(&stack, Some(Point::new(20., 20.)), checkbox));
+
+        let _window = stack.enter_region(Point::new(200., 100.));
+        assert!(hovered(&stack, Some(Point::new(220., 120.)), checkbox));
+        assert!(!hovered(&stack, Some(Point::new(20., 20.)), checkbox));
+
+        // Regions inside regions add up
+        let _outer = stack.enter_region(Point::new(50., 0.));
+        assert!(hovered(&stack, Some(Point::new(270., 120.)), checkbox));
+        assert!(!hovered(&stack, Some(Point::new(220., 120.)), checkbox));
+        assert!(!hovered(&stack, None, checkbox));
+    }
+}